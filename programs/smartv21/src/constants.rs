@@ -5,3 +5,45 @@ pub const CONFIG_SEED: &str = "config";
 pub const POOL_LOAN_SEED: &str = "pool_loan";
 pub const LP_TOKEN_SEED: &str = "lp_token";
 pub const VAULT_SEED: &str = "vault";
+pub const LOAN_TIER_SEED: &str = "loan_tier";
+pub const LOAN_DURATIONS_SEED: &str = "loan_durations";
+pub const TREASURY_SEED: &str = "treasury";
+pub const INSURANCE_SEED: &str = "insurance";
+pub const SHARE_MINT_SEED: &str = "share_mint";
+pub const WITHDRAWAL_QUEUE_SEED: &str = "withdrawal_queue";
+pub const WITHDRAWAL_TICKET_SEED: &str = "withdrawal_ticket";
+pub const CREATOR_STATS_SEED: &str = "creator_stats";
+pub const PENDING_ACTION_SEED: &str = "pending_action";
+pub const ADMIN_PROPOSAL_SEED: &str = "admin_proposal";
+pub const LIQUIDATION_AUCTION_SEED: &str = "liquidation_auction";
+pub const PROTOCOL_SHARES_SEED: &str = "protocol_shares";
+
+pub const MAX_LOAN_DURATIONS: usize = 8;
+pub const MAX_WITHDRAWAL_REQUESTS: usize = 32;
+pub const MIN_WITHDRAWAL_REQUEST: u64 = 100_000_000; // 0.1 wrap sol, keeps dust requests from filling the queue
+pub const MAX_GUARDIANS: usize = 5;
+pub const MAX_ADMIN_SIGNERS: usize = 7;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+pub const MAX_INTEREST_RATE_BPS: u64 = 100_000; // 1000% per year
+pub const MAX_TIMELOCK_DELAY: i64 = 30 * 24 * 60 * 60;
+pub const MAX_LIQUIDATION_BOUNTY_BPS: u64 = 2_000;
+pub const MAX_GRACE_PERIOD: i64 = 7 * 24 * 60 * 60;
+pub const MAX_LATE_FEE_BPS: u64 = 5_000;
+pub const MAX_LIQUIDATION_PENALTY_BPS: u64 = 5_000;
+pub const MAX_AUCTION_DECAY_PERIOD: i64 = 7 * 24 * 60 * 60;
+pub const MAX_AUCTION_DURATION: i64 = 14 * 24 * 60 * 60;
+pub const MAX_AUCTION_START_PRICE_BPS: u64 = 50_000; // 5x the amount owed
+pub const TWAP_WINDOW: u64 = 30 * 60;
+pub const MAX_LIQUIDATION_SLIPPAGE_BPS: u64 = 2_000;
+pub const DEFAULT_LIQUIDATION_SLIPPAGE_BPS: u64 = 500;
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000; // Raydium trade fee rates are in millionths
+pub const VIRTUAL_LIQUIDITY: u64 = 1_000_000; // Virtual shares and assets added to the share price
+
+// Operations that can be paused individually
+pub const PAUSE_NEW_LOANS: u8 = 1 << 0;
+pub const PAUSE_REPAYMENTS: u8 = 1 << 1;
+pub const PAUSE_LIQUIDATIONS: u8 = 1 << 2;
+pub const PAUSE_VAULT_WITHDRAWALS: u8 = 1 << 3;
+pub const PAUSE_DEPOSITS: u8 = 1 << 4;
+pub const PAUSE_ALL: u8 = PAUSE_NEW_LOANS | PAUSE_REPAYMENTS | PAUSE_LIQUIDATIONS | PAUSE_VAULT_WITHDRAWALS | PAUSE_DEPOSITS;
@@ -8,9 +8,9 @@ pub enum ErrorCode {
     InsufficientBalance,
     #[msg("Please deposit the total supply of token")]
     InsufficientTokenBalance,
-    #[msg("Loan duration is invalid. It should be 1 day")]
+    #[msg("Loan duration is invalid for the loan tier")]
     InvalidDuration,
-    #[msg("Loan sol amount is invalid. It should be one of 2 / 5 / 10 / 20 SOL")]
+    #[msg("Loan sol amount doesn't match the loan tier")]
     InvalidInitSolAmount,
     #[msg("invalid wrapped sol mint address")]
     InvalidWrappedSolMint,
@@ -32,4 +32,104 @@ pub enum ErrorCode {
     MintAuthorityNotRevoked,
     #[msg("Token freeze authority must be revoked")]
     FreezeAuthorityNotRevoked,
+    #[msg("Loan tier is disabled")]
+    LoanTierDisabled,
+    #[msg("Invalid loan tier account")]
+    InvalidLoanTier,
+    #[msg("Loan has reached the maximum number of extensions")]
+    MaxExtensionsReached,
+    #[msg("Invalid LP token amount")]
+    InvalidLpAmount,
+    #[msg("Invalid interest rate")]
+    InvalidInterestRate,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Invalid referrer account")]
+    InvalidReferrer,
+    #[msg("Invalid config account")]
+    InvalidConfig,
+    #[msg("Config account is already up to date")]
+    ConfigAlreadyMigrated,
+    #[msg("Not enough idle liquidity in the service vault")]
+    InsufficientLiquidity,
+    #[msg("Invalid share amount")]
+    InvalidShareAmount,
+    #[msg("Existing vault assets must be seeded before accepting deposits")]
+    SharesNotSeeded,
+    #[msg("Shares have already been seeded")]
+    SharesAlreadySeeded,
+    #[msg("Withdrawal queue is full")]
+    WithdrawalQueueFull,
+    #[msg("Withdrawal request not found")]
+    WithdrawalRequestNotFound,
+    #[msg("Withdrawal request is not filled yet")]
+    WithdrawalNotFilled,
+    #[msg("Vault utilization cap exceeded")]
+    UtilizationCapExceeded,
+    #[msg("Maximum number of active loans reached")]
+    MaxActiveLoansReached,
+    #[msg("Maximum number of active loans for this creator reached")]
+    CreatorLoanCapReached,
+    #[msg("Lending cap for the current window exceeded")]
+    LendingWindowCapExceeded,
+    #[msg("Invalid lending caps")]
+    InvalidLendingCaps,
+    #[msg("Invalid admin")]
+    InvalidAdmin,
+    #[msg("Invalid pause mask")]
+    InvalidPauseMask,
+    #[msg("Maximum number of guardians reached")]
+    MaxGuardiansReached,
+    #[msg("Guardian already exists")]
+    GuardianAlreadyExists,
+    #[msg("Guardian not found")]
+    GuardianNotFound,
+    #[msg("No admin transfer pending")]
+    NoPendingAdmin,
+    #[msg("This change must be queued through the timelock")]
+    TimelockRequired,
+    #[msg("Timelock delay has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Invalid timelock")]
+    InvalidTimelock,
+    #[msg("Invalid admin action")]
+    InvalidAdminAction,
+    #[msg("Missing account")]
+    MissingAccount,
+    #[msg("Invalid recipient")]
+    InvalidRecipient,
+    #[msg("This change must go through an admin proposal")]
+    MultisigRequired,
+    #[msg("Invalid admin signers")]
+    InvalidAdminSigners,
+    #[msg("Proposal already approved by this signer")]
+    AlreadyApproved,
+    #[msg("Proposal does not have enough approvals")]
+    ThresholdNotReached,
+    #[msg("Invalid liquidation parameters")]
+    InvalidLiquidationParams,
+    #[msg("Loan is in its grace period")]
+    LoanInGracePeriod,
+    #[msg("Invalid auction parameters")]
+    InvalidAuctionParams,
+    #[msg("Liquidation auctions are disabled")]
+    AuctionsDisabled,
+    #[msg("Loan is being auctioned")]
+    AuctionInProgress,
+    #[msg("Auction price is above the maximum price")]
+    AuctionPriceAboveMax,
+    #[msg("Pool price oracle unavailable")]
+    OracleUnavailable,
+    #[msg("Invalid health threshold")]
+    InvalidHealthThreshold,
+    #[msg("Invalid pool loan account")]
+    InvalidPoolLoan,
+    #[msg("Pool loan account is already up to date")]
+    PoolLoanAlreadyMigrated,
+    #[msg("Withdrawal request is below the minimum")]
+    WithdrawalTooSmall,
+    #[msg("Liquidation recovers too little compared to the pool TWAP")]
+    LiquidationPriceDeviation,
+    #[msg("Auction has expired")]
+    AuctionExpired,
 }
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::{ constants::MAX_ADMIN_SIGNERS, state::{AdminAction, AuctionDecay, Role} };
+
 // Event emitted on loan liquidation
 #[event]
 pub struct LoanLiquidatedEvent {
@@ -7,5 +9,282 @@ pub struct LoanLiquidatedEvent {
     pub user: Pubkey,
     pub liquidator: Pubkey,
     pub amount: u64,
+    pub bounty: u64, // wrap sol kept by the liquidator
+    pub penalty: u64, // wrap sol sent to the treasury
+    pub creator_sol_amount: u64, // wrap sol returned to the creator
+    pub creator_token_amount: u64,
+    pub tokens_swapped: u64, // tokens sold for sol to cover the debt
+    pub sol_from_swap: u64,
+    pub shortfall: u64, // debt left unrecovered
+    pub early_liquidation: bool, // liquidated before expiry on the health check
+    pub timestamp: i64,
+}
+
+// Event emitted when a liquidation leaves principal unrecovered
+#[event]
+pub struct BadDebtRecordedEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64, // principal lost
+    pub bad_debt_outstanding: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when the insurance fund pays bad debt back into the vault
+#[event]
+pub struct BadDebtCoveredEvent {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub bad_debt_outstanding: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when protocol revenue is withdrawn from the treasury
+#[event]
+pub struct TreasuryWithdrawnEvent {
+    pub authority: Pubkey,
+    pub recipient: Pubkey, // receiving token account
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when the escrowed lp of an expired loan is put up for auction
+#[event]
+pub struct LiquidationAuctionStartedEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub starter: Pubkey,
+    pub lp_amount: u64,
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub decay: AuctionDecay,
+    pub decay_period: i64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+// Event emitted when an auction nobody bid on is closed after it expired
+#[event]
+pub struct LiquidationAuctionClosedEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub starter: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when a bidder buys the escrowed lp and the loan is settled
+#[event]
+pub struct LiquidationAuctionSettledEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub bidder: Pubkey,
+    pub lp_amount: u64,
+    pub price: u64, // wrap sol paid by the bidder
+    pub penalty: u64, // wrap sol sent to the treasury
+    pub creator_sol_amount: u64, // wrap sol returned to the creator
+    pub shortfall: u64, // debt left unrecovered
+    pub timestamp: i64,
+}
+
+// Event emitted when a loan is extended
+#[event]
+pub struct LoanExtendedEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub fee: u64,
+    pub new_deadline: i64,
+    pub extension_count: u8,
+    pub timestamp: i64,
+}
+
+// Event emitted on every repayment towards a loan
+#[event]
+pub struct LoanRepaidEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub interest_paid: u64,
+    pub late_fee: u64, // Late fee charged by this repayment, included in interest_paid
+    pub principal_outstanding: u64,
+    pub is_repaid: bool,
+    pub timestamp: i64,
+}
+
+// Event emitted when a fee is split between the fee recipients
+#[event]
+pub struct FeeDistributedEvent {
+    pub pool: Pubkey,
+    pub payer: Pubkey,
+    pub referrer: Option<Pubkey>,
+    pub treasury_amount: u64,
+    pub insurance_amount: u64,
+    pub referrer_amount: u64,
+    pub vault_amount: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a liquidity provider deposits into the service vault
+#[event]
+pub struct LiquidityDepositedEvent {
+    pub provider: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a liquidity provider redeems shares
+#[event]
+pub struct LiquidityRedeemedEvent {
+    pub provider: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a withdrawal is queued
+#[event]
+pub struct WithdrawalRequestedEvent {
+    pub request_id: u64,
+    pub owner: Pubkey,
+    pub shares: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when idle liquidity is reserved for a queued withdrawal
+#[event]
+pub struct WithdrawalFilledEvent {
+    pub request_id: u64,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a filled withdrawal is paid out
+#[event]
+pub struct WithdrawalClaimedEvent {
+    pub request_id: u64,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when an admin transfer is proposed
+#[event]
+pub struct AdminProposedEvent {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when the proposed admin accepts the transfer
+#[event]
+pub struct AdminTransferredEvent {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when the syncer or verifier is replaced
+#[event]
+pub struct RoleUpdatedEvent {
+    pub role: Role,
+    pub previous: Pubkey,
+    pub new: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when operations are paused or unpaused
+#[event]
+pub struct PauseUpdatedEvent {
+    pub authority: Pubkey,
+    pub paused_operations: u8,
+    pub timestamp: i64,
+}
+
+// Event emitted when a guardian is added or removed
+#[event]
+pub struct GuardianUpdatedEvent {
+    pub guardian: Pubkey,
+    pub is_added: bool,
+    pub timestamp: i64,
+}
+
+// Event emitted when a pending admin transfer is cancelled
+#[event]
+pub struct AdminTransferCancelledEvent {
+    pub pending_admin: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when an admin action is queued behind the timelock
+#[event]
+pub struct ActionQueuedEvent {
+    pub action_id: u64,
+    pub action: AdminAction,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a queued admin action is executed
+#[event]
+pub struct ActionExecutedEvent {
+    pub action_id: u64,
+    pub action: AdminAction,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when a queued admin action is cancelled
+#[event]
+pub struct ActionCancelledEvent {
+    pub action_id: u64,
+    pub action: AdminAction,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when the admin signers or threshold change
+#[event]
+pub struct AdminSignersUpdatedEvent {
+    pub signers: [Pubkey; MAX_ADMIN_SIGNERS],
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+// Event emitted when an admin signer proposes an action
+#[event]
+pub struct ProposalCreatedEvent {
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    pub timestamp: i64,
+}
+
+// Event emitted when an admin signer approves a proposal
+#[event]
+pub struct ProposalApprovedEvent {
+    pub proposal_id: u64,
+    pub signer: Pubkey,
+    pub approvals: u8,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+// Event emitted when an approved proposal is executed
+#[event]
+pub struct ProposalExecutedEvent {
+    pub proposal_id: u64,
+    pub action: AdminAction,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+// Event emitted when a proposal is cancelled
+#[event]
+pub struct ProposalCancelledEvent {
+    pub proposal_id: u64,
+    pub cancelled_by: Pubkey,
     pub timestamp: i64,
 }
@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::{apply_config_action, UpdateConfig} };
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        constraint = config.pending_admin == new_admin.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    pub new_admin: Signer<'info>,
+}
+
+// Emergency actions open to the admin and the guardians
+#[derive(Accounts)]
+pub struct GuardianAction<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        constraint = config.admin == authority.key() || config.is_guardian(&authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    pub authority: Signer<'info>,
+}
+
+// First step of the admin transfer, the proposed admin has to accept before it takes effect
+pub fn propose_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::ProposeAdmin { new_admin },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn cancel_admin_transfer(ctx: Context<GuardianAction>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(config.pending_admin != Pubkey::default(), ErrorCode::NoPendingAdmin);
+
+    let pending_admin = config.pending_admin;
+    config.pending_admin = Pubkey::default();
+    msg!("Admin transfer to {} cancelled by {}", pending_admin, ctx.accounts.authority.key());
+
+    emit!(AdminTransferCancelledEvent {
+        pending_admin,
+        cancelled_by: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let previous_admin = config.admin;
+    config.admin = config.pending_admin;
+    config.pending_admin = Pubkey::default();
+    msg!("Admin transferred from {} to {}", previous_admin, config.admin);
+
+    emit!(AdminTransferredEvent {
+        previous_admin,
+        new_admin: config.admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_syncer(ctx: Context<UpdateConfig>, syncer: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::SetSyncer { syncer },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn set_verifier(ctx: Context<UpdateConfig>, verifier: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::SetVerifier { verifier },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn add_guardian(ctx: Context<UpdateConfig>, guardian: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::AddGuardian { guardian },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn remove_guardian(ctx: Context<UpdateConfig>, guardian: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::RemoveGuardian { guardian },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+// Halt the operations in the mask, operations already paused stay paused
+pub fn pause(ctx: Context<GuardianAction>, operations: u8) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let paused_operations = config.paused_operations | operations;
+    config.set_paused_operations(paused_operations)?;
+    msg!("Paused operations {:#07b}, now paused {:#07b}", operations, config.paused_operations);
+
+    emit!(PauseUpdatedEvent {
+        authority: ctx.accounts.authority.key(),
+        paused_operations: config.paused_operations,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Only the admin, or the admin signers under the multisig, can resume operations
+pub fn unpause(ctx: Context<UpdateConfig>, operations: u8) -> Result<()> {
+    apply_config_action(
+        &mut ctx.accounts.config,
+        &AdminAction::Unpause { operations },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+// Replace the admin signers, a threshold above 0 moves privileged changes to multisig proposals.
+// Once the timelock or the multisig is on the signers can only change through them
+pub fn set_admin_signers(
+    ctx: Context<UpdateConfig>,
+    signers: [Pubkey; MAX_ADMIN_SIGNERS],
+    threshold: u8,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::SetAdminSigners { signers, threshold },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
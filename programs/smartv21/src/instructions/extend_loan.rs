@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_interface::{Mint, TokenAccount},
+};
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::transfer_tokens };
+
+#[derive(Accounts)]
+pub struct ExtendLoan<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [POOL_LOAN_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+        has_one = user @ ErrorCode::Unauthorized,
+    )]
+    pub pool_loan: Box<Account<'info, PoolLoan>>,
+
+    /// Loan tier the pool was created with
+    #[account(
+        seeds = [LOAN_TIER_SEED.as_bytes(), &pool_loan.tier_id.to_le_bytes()],
+        bump,
+    )]
+    pub loan_tier: Box<Account<'info, LoanTier>>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury receiving its share of the fee
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED.as_bytes()],
+        bump,
+        constraint = treasury_vault.mint == service_vault.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Insurance fund receiving its share of the fee
+    #[account(
+        mut,
+        seeds = [INSURANCE_SEED.as_bytes()],
+        bump,
+        constraint = insurance_vault.mint == service_vault.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub insurance_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Only used to derive the pool loan address
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// Creator of the pool, pays the extension fee
+    pub user: Signer<'info>,
+
+    /// The user's wrapped sol account paying the extension fee
+    #[account(
+        mut,
+        token::mint = wsol_mint,
+        token::authority = user
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Wrapped sol mint
+    #[account(address = service_vault.mint)]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// Push the deadline of an active loan out by the tier extension duration
+pub fn extend_loan(ctx: Context<ExtendLoan>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let pool_loan = &mut ctx.accounts.pool_loan;
+    let loan_tier = &ctx.accounts.loan_tier;
+
+    config.require_not_paused(PAUSE_NEW_LOANS)?;
+    require!(!pool_loan.is_repaid, ErrorCode::LoanAlreadyRepaid);
+
+    // Only active loans can be extended
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        current_time <= pool_loan.deadline(),
+        ErrorCode::LoanExpired
+    );
+    require!(
+        pool_loan.extension_count < loan_tier.max_extensions,
+        ErrorCode::MaxExtensionsReached
+    );
+
+    let extension_fee = loan_tier.extension_fee;
+    let fee_split = config.split_fee(extension_fee, false)?;
+    let transfer_share = |to, amount| {
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            to,
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.wsol_mint.to_account_info(),
+            ctx.accounts.wsol_mint.decimals,
+            amount,
+        )
+    };
+
+    transfer_share(ctx.accounts.service_vault.to_account_info(), fee_split.vault)?;
+    transfer_share(ctx.accounts.treasury_vault.to_account_info(), fee_split.treasury)?;
+    transfer_share(ctx.accounts.insurance_vault.to_account_info(), fee_split.insurance)?;
+    config.record_fee(fee_split.vault);
+    msg!("Extension fee {} paid, {} sent to service vault", extension_fee, fee_split.vault);
+
+    pool_loan.loan_duration = pool_loan.loan_duration
+        .checked_add(loan_tier.extension_duration)
+        .ok_or(ErrorCode::InvalidDuration)?;
+    pool_loan.extension_count += 1;
+
+    let new_deadline = pool_loan.deadline();
+    msg!("Loan extended to {} ({} of {} extensions)", new_deadline, pool_loan.extension_count, loan_tier.max_extensions);
+
+    emit!(LoanExtendedEvent {
+        pool: pool_loan.pool,
+        user: pool_loan.user,
+        fee: extension_fee,
+        new_deadline,
+        extension_count: pool_loan.extension_count,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
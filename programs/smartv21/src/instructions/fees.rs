@@ -0,0 +1,246 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_2022::{transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::fill_withdrawal_queue };
+
+// Create the treasury and insurance fund accounts receiving their share of the fees
+#[derive(Accounts)]
+pub struct InitializeFeeVaults<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(address = service_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [TREASURY_SEED.as_bytes()],
+        bump,
+        token::mint = token_mint,
+        token::authority = config,
+    )]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [INSURANCE_SEED.as_bytes()],
+        bump,
+        token::mint = token_mint,
+        token::authority = config,
+    )]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+// Withdraw protocol revenue from the treasury
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+
+    #[account(address = treasury_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED.as_bytes()],
+        bump
+    )]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// Pay outstanding bad debt back into the service vault from the insurance fund, open to anyone
+#[derive(Accounts)]
+pub struct CoverBadDebt<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Account<'info, Config>,
+
+    pub authority: Signer<'info>,
+
+    #[account(address = service_vault.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [INSURANCE_SEED.as_bytes()],
+        bump,
+        constraint = insurance_vault.mint == service_vault.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_QUEUE_SEED.as_bytes()],
+        bump,
+    )]
+    pub withdrawal_queue: Box<Account<'info, WithdrawalQueue>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn initialize_fee_vaults(ctx: Context<InitializeFeeVaults>) -> Result<()> {
+    msg!(
+        "Treasury {} and insurance fund {} initialized",
+        ctx.accounts.treasury_vault.key(),
+        ctx.accounts.insurance_vault.key()
+    );
+
+    Ok(())
+}
+
+pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    require!(amount <= ctx.accounts.treasury_vault.amount, ErrorCode::InsufficientBalance);
+
+    // Same timelock rule as vault withdrawals, large amounts have to be queued
+    let current_time = Clock::get()?.unix_timestamp;
+    ctx.accounts.config.record_direct_withdrawal(amount, current_time)?;
+
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[CONFIG_SEED.as_bytes()],
+        ctx.program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.treasury_vault.to_account_info(),
+                to: ctx.accounts.recipient_token_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+            },
+            &[signer_seeds]
+        ),
+        amount,
+        ctx.accounts.token_mint.decimals
+    )?;
+    msg!("Withdraw {} tokens from treasury", amount);
+
+    emit!(TreasuryWithdrawnEvent {
+        authority: ctx.accounts.admin.key(),
+        recipient: ctx.accounts.recipient_token_account.key(),
+        amount,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+// Cover as much of the outstanding bad debt as the insurance fund holds
+pub fn cover_bad_debt(ctx: Context<CoverBadDebt>) -> Result<()> {
+    let amount = ctx.accounts.config.bad_debt_outstanding.min(ctx.accounts.insurance_vault.amount);
+    require!(amount > 0, ErrorCode::InsufficientBalance);
+
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[CONFIG_SEED.as_bytes()],
+        ctx.program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.insurance_vault.to_account_info(),
+                to: ctx.accounts.service_vault.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+            },
+            &[signer_seeds]
+        ),
+        amount,
+        ctx.accounts.token_mint.decimals
+    )?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let config = &mut ctx.accounts.config;
+    config.record_bad_debt_covered(amount);
+    msg!("Insurance fund covered {} wrap sol of bad debt, {} outstanding", amount, config.bad_debt_outstanding);
+
+    emit!(BadDebtCoveredEvent {
+        authority: ctx.accounts.authority.key(),
+        amount,
+        bad_debt_outstanding: config.bad_debt_outstanding,
+        timestamp: current_time,
+    });
+
+    fill_withdrawal_queue(config, &mut ctx.accounts.withdrawal_queue, current_time);
+
+    Ok(())
+}
+
+// Checked transfer out of a token account the authority signs for, a zero amount is skipped.
+// Used for fee shares and for the sol and tokens a settlement hands out
+pub fn transfer_tokens<'info>(
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    decimals: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    transfer_checked(
+        CpiContext::new(
+            token_program,
+            TransferChecked {
+                from,
+                to,
+                authority,
+                mint,
+            },
+        ),
+        amount,
+        decimals
+    )
+}
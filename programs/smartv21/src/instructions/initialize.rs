@@ -12,7 +12,7 @@ use raydium_cpmm_cpi::{
 };
 use spl_memo::solana_program::program::invoke_signed;
 
-use crate::{ constants::*, state::*, error::ErrorCode};
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::{apply_config_action, transfer_tokens} };
 use std::str::FromStr;
 
 // Contexts
@@ -52,7 +52,21 @@ pub struct UpdateServiceFee<'info> {
         mut,
         seeds = [CONFIG_SEED.as_bytes()],
         bump,
-        has_one = admin
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
     )]
     pub config: Account<'info, Config>,
     pub admin: Signer<'info>,
@@ -72,6 +86,30 @@ pub struct CreateLiquidityPool<'info> {
     )]
     pub pool_loan: Box<Account<'info, PoolLoan>>,
 
+    /// Loan tier the creator borrows under
+    #[account(
+        seeds = [LOAN_TIER_SEED.as_bytes(), &loan_tier.tier_id.to_le_bytes()],
+        bump,
+    )]
+    pub loan_tier: Box<Account<'info, LoanTier>>,
+
+    /// Loans outstanding for the creator
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + CreatorStats::LEN,
+        seeds = [CREATOR_STATS_SEED.as_bytes(), creator.key().as_ref()],
+        bump,
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    /// Loan durations creators can choose from, without it any duration within the tier bounds is allowed
+    #[account(
+        seeds = [LOAN_DURATIONS_SEED.as_bytes()],
+        bump,
+    )]
+    pub loan_durations: Option<Box<Account<'info, LoanDurations>>>,
+
     #[account(
         mut,
         seeds = [VAULT_SEED.as_bytes()],
@@ -79,6 +117,32 @@ pub struct CreateLiquidityPool<'info> {
     )]
     pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    /// Treasury receiving its share of the fee
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED.as_bytes()],
+        bump,
+        constraint = treasury_vault.mint == service_vault.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Insurance fund receiving its share of the fee
+    #[account(
+        mut,
+        seeds = [INSURANCE_SEED.as_bytes()],
+        bump,
+        constraint = insurance_vault.mint == service_vault.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub insurance_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Optional wrapped sol account of the referrer receiving its share of the fee
+    #[account(
+        mut,
+        constraint = referrer_token_account.mint == service_vault.mint @ ErrorCode::InvalidReferrer,
+        constraint = referrer_token_account.owner != creator.key() @ ErrorCode::InvalidReferrer
+    )]
+    pub referrer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
     pub cp_swap_program: Program<'info, RaydiumCpmm>,
     /// Address paying to create the pool. Can be anyone
     #[account(mut)]
@@ -275,6 +339,7 @@ pub fn initialize(
     config.verifier = verifier;
     config.service_fee = service_fee;
     config.is_paused = false;
+    config.liquidation_slippage_bps = DEFAULT_LIQUIDATION_SLIPPAGE_BPS;
 
     Ok(())
 }
@@ -284,12 +349,176 @@ pub fn update_service_fee(ctx: Context<UpdateServiceFee>, new_fixed_fee: u64) ->
 
     // Ensure the caller is the admin
     require!(ctx.accounts.admin.key() == config.admin, ErrorCode::Unauthorized);
+    config.require_no_timelock()?;
     // Update the fixed service fee
     config.service_fee = new_fixed_fee;
     
     Ok(())
 }
 
+pub fn update_fee_schedule(
+    ctx: Context<UpdateConfig>,
+    fixed_fee: u64,
+    fee_bps: u64,
+    min_fee: u64,
+    max_fee: u64,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateFeeSchedule { fixed_fee, fee_bps, min_fee, max_fee },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_fee_split(
+    ctx: Context<UpdateConfig>,
+    treasury_fee_bps: u64,
+    insurance_fee_bps: u64,
+    referrer_fee_bps: u64,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateFeeSplit { treasury_fee_bps, insurance_fee_bps, referrer_fee_bps },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_interest_rate(ctx: Context<UpdateConfig>, interest_rate_bps: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateInterestRate { interest_rate_bps },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_lending_caps(
+    ctx: Context<UpdateConfig>,
+    max_utilization_bps: u64,
+    max_active_loans: u64,
+    max_loans_per_creator: u64,
+    max_lent_per_window: u64,
+    lending_window: i64,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateLendingCaps {
+            max_utilization_bps,
+            max_active_loans,
+            max_loans_per_creator,
+            max_lent_per_window,
+            lending_window,
+        },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_liquidation_bounty(ctx: Context<UpdateConfig>, bounty_bps: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateLiquidationBounty { bounty_bps },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_liquidation_penalty(ctx: Context<UpdateConfig>, penalty_bps: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateLiquidationPenalty { penalty_bps },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_liquidation_slippage(ctx: Context<UpdateConfig>, slippage_bps: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateLiquidationSlippage { slippage_bps },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_auction_params(
+    ctx: Context<UpdateConfig>,
+    decay: AuctionDecay,
+    decay_period: i64,
+    start_price_bps: u64,
+    floor_price_bps: u64,
+    max_duration: i64,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateAuctionParams { decay, decay_period, start_price_bps, floor_price_bps, max_duration },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_health_threshold(ctx: Context<UpdateConfig>, health_threshold_bps: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateHealthThreshold { health_threshold_bps },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn update_grace_period(ctx: Context<UpdateConfig>, grace_period: i64, late_fee_bps: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::UpdateGracePeriod { grace_period, late_fee_bps },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+// Turn the timelock on, once on it can only be changed through the timelock itself
+pub fn set_timelock(ctx: Context<UpdateConfig>, delay: i64, large_withdrawal_threshold: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.require_no_timelock()?;
+
+    apply_config_action(
+        config,
+        &AdminAction::SetTimelock { delay, large_withdrawal_threshold },
+        ctx.accounts.admin.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
 
 pub fn create_liquidity_pool(
     ctx: Context<CreateLiquidityPool>,
@@ -306,13 +535,14 @@ pub fn create_liquidity_pool(
     // Verify program is not paused
     let config = &mut ctx.accounts.config;
     let pool_loan = &mut ctx.accounts.pool_loan;
+    let loan_tier = &ctx.accounts.loan_tier;
 
-    require!(!config.is_paused, ErrorCode::ProgramPaused);
+    config.require_not_paused(PAUSE_NEW_LOANS)?;
+    require!(loan_tier.is_enabled, ErrorCode::LoanTierDisabled);
 
     if is_token0_wrapped_sol {
         msg!("Token0 is Wrapped SOL");
-        require!(config.amount >= init_amount_0, ErrorCode::InsufficientBalance);
-        require!(init_amount_0 == 2_000_000_000 || init_amount_0 == 5_000_000_000 || init_amount_0 == 10_000_000_000 || init_amount_0 == 20_000_000_000, ErrorCode::InvalidInitSolAmount);
+        require!(init_amount_0 == loan_tier.sol_amount, ErrorCode::InvalidInitSolAmount);
 
         let token_mint = &ctx.accounts.token_1_mint;
         let total_supply = token_mint.supply;
@@ -332,8 +562,7 @@ pub fn create_liquidity_pool(
 
     if is_token1_wrapped_sol {
         msg!("Token1 is Wrapped SOL");
-        require!(config.amount >= init_amount_1, ErrorCode::InsufficientBalance);
-        require!(init_amount_1 == 2_000_000_000 || init_amount_1 == 5_000_000_000 || init_amount_1 == 10_000_000_000 || init_amount_1 == 20_000_000_000, ErrorCode::InvalidInitSolAmount);
+        require!(init_amount_1 == loan_tier.sol_amount, ErrorCode::InvalidInitSolAmount);
 
         let token_mint = &ctx.accounts.token_0_mint;
         let total_supply = token_mint.supply;
@@ -351,64 +580,90 @@ pub fn create_liquidity_pool(
         pool_loan.init_token_amount = init_amount_0;
     }
 
-    require!(loan_duration == 60 *60 *24, ErrorCode::InvalidDuration);
+    // Only the tier fee scales with the chosen duration, interest accrues per second
+    // at the same rate so a longer loan simply pays interest for longer
+    let duration_option = match ctx.accounts.loan_durations.as_ref() {
+        Some(loan_durations) => *loan_durations
+            .find(loan_duration)
+            .ok_or(ErrorCode::InvalidDuration)?,
+        None => LoanDurationOption {
+            duration: loan_duration,
+            fee_multiplier_bps: BPS_DENOMINATOR as u32,
+        },
+    };
+    require!(
+        loan_duration >= loan_tier.min_duration && loan_duration <= loan_tier.max_duration,
+        ErrorCode::InvalidDuration
+    );
 
     pool_loan.user = ctx.accounts.creator.key();
     pool_loan.pool = ctx.accounts.pool_state.key();
     pool_loan.lp_mint = ctx.accounts.lp_mint.key();
     pool_loan.loan_start_time = Clock::get()?.unix_timestamp;
-    pool_loan.loan_duration = 60 * 60 * 24; // 24 hours in seconds - fixed time
+    pool_loan.loan_duration = loan_duration;
     pool_loan.is_repaid = false;
+    pool_loan.principal_outstanding = pool_loan.init_sol_amount;
+    pool_loan.interest_rate_bps = config.interest_rate_bps;
+    pool_loan.interest_accrued = 0;
+    pool_loan.last_accrual_time = pool_loan.loan_start_time;
+    pool_loan.late_fee_charged = false;
+    pool_loan.in_auction = false;
+    pool_loan.shortfall = 0;
+    pool_loan.bad_debt = 0;
+    pool_loan.tier_id = loan_tier.tier_id;
+
+    let creator_stats = &mut ctx.accounts.creator_stats;
+    creator_stats.creator = ctx.accounts.creator.key();
+    creator_stats.record_loan(config.max_loans_per_creator)?;
     // Send dynamic fee to the service vault as upfront
     // Calculate the dynamic fee
-    let dynamic_fee = config.service_fee;
-        // .checked_add(
-        //     pool_loan.init_sol_amount
-        //         .checked_mul(30 as u64)
-        //         .ok_or(ErrorCode::InvalidFee)?
-        //         .checked_div(10000)
-        //         .ok_or(ErrorCode::InvalidFee)?,
-        // )
-        // .ok_or(ErrorCode::InvalidFee)?;
+    let tier_fee = loan_tier.fee_for(&duration_option).ok_or(ErrorCode::InvalidFee)?;
+    let dynamic_fee = config.compute_service_fee(pool_loan.init_sol_amount)?
+        .checked_add(tier_fee)
+        .ok_or(ErrorCode::InvalidFee)?;
 
     msg!(
         "Dynamic fee for this pool creation is {} lamports",
         dynamic_fee
     );
 
-    if is_token0_wrapped_sol {
-        let _= transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.creator_token_0.to_account_info(),
-                    to: ctx.accounts.service_vault.to_account_info(),
-                    authority: ctx.accounts.creator.to_account_info(),
-                    mint: ctx.accounts.token_0_mint.to_account_info(),
-                },
-            ),
-            dynamic_fee,
-            ctx.accounts.token_0_mint.decimals
-        );
-        msg!("Dynamic fee {} sent to service vault", dynamic_fee);
-        config.amount += dynamic_fee;
+    let fee_split = config.split_fee(dynamic_fee, ctx.accounts.referrer_token_account.is_some())?;
+    let (creator_wsol_account, wsol_mint) = if is_token0_wrapped_sol {
+        (&ctx.accounts.creator_token_0, &ctx.accounts.token_0_mint)
     } else {
-        let _= transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.creator_token_1.to_account_info(),
-                    to: ctx.accounts.service_vault.to_account_info(),
-                    authority: ctx.accounts.creator.to_account_info(),
-                    mint: ctx.accounts.token_1_mint.to_account_info(),
-                },
-            ),
-            dynamic_fee,
-            ctx.accounts.token_1_mint.decimals
-        );
-        msg!("Dynamic fee {} sent to service vault", dynamic_fee);
-        config.amount += dynamic_fee;
+        (&ctx.accounts.creator_token_1, &ctx.accounts.token_1_mint)
+    };
+    let transfer_share = |to, amount| {
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            creator_wsol_account.to_account_info(),
+            to,
+            ctx.accounts.creator.to_account_info(),
+            wsol_mint.to_account_info(),
+            wsol_mint.decimals,
+            amount,
+        )
+    };
+
+    transfer_share(ctx.accounts.service_vault.to_account_info(), fee_split.vault)?;
+    transfer_share(ctx.accounts.treasury_vault.to_account_info(), fee_split.treasury)?;
+    transfer_share(ctx.accounts.insurance_vault.to_account_info(), fee_split.insurance)?;
+    if let Some(referrer_token_account) = &ctx.accounts.referrer_token_account {
+        transfer_share(referrer_token_account.to_account_info(), fee_split.referrer)?;
     }
+    msg!("Dynamic fee {} sent to service vault", fee_split.vault);
+    config.record_fee(fee_split.vault);
+
+    emit!(FeeDistributedEvent {
+        pool: ctx.accounts.pool_state.key(),
+        payer: ctx.accounts.creator.key(),
+        referrer: ctx.accounts.referrer_token_account.as_ref().map(|account| account.owner),
+        treasury_amount: fee_split.treasury,
+        insurance_amount: fee_split.insurance,
+        referrer_amount: fee_split.referrer,
+        vault_amount: fee_split.vault,
+        timestamp: pool_loan.loan_start_time,
+    });
 
     // Rent init_sol_amount  wrap sol from service vault to user
     let (_vault_authority, vault_bump) = Pubkey::find_program_address(
@@ -434,7 +689,7 @@ pub fn create_liquidity_pool(
             ctx.accounts.token_0_mint.decimals
         );
         
-        config.amount -= init_amount_0;
+        config.record_loan(init_amount_0, pool_loan.loan_start_time)?;
         msg!("Rent {} wrapsol from vault to user", init_amount_0);
     } else {
         let _= transfer_checked(
@@ -451,7 +706,7 @@ pub fn create_liquidity_pool(
             init_amount_1,
             ctx.accounts.token_1_mint.decimals
         );
-        config.amount -= init_amount_1;
+        config.record_loan(init_amount_1, pool_loan.loan_start_time)?;
         msg!("Rent {} wrapsol from vault to user", init_amount_1);
     }
     msg!("The amount of service vault is {}", config.amount);
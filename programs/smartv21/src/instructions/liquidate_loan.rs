@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
+    associated_token::AssociatedToken,
     token::Token,
     token_2022::{transfer_checked, TransferChecked},
     token_interface::{Mint, TokenAccount, Token2022},
@@ -7,11 +8,11 @@ use anchor_spl::{
 use raydium_cpmm_cpi::{
     cpi,
     program::RaydiumCpmm,
-    states::PoolState,
+    states::{AmmConfig, ObservationState, PoolState},
 };
 
 
-use crate::{ constants::*, state::*, error::ErrorCode, event::* };
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::{fill_withdrawal_queue, transfer_tokens}, oracle::{expected_swap_sol_out, lp_sol_value, spot_price_0_x32, twap_price_0_x32} };
 use std::str::FromStr;
 
 #[derive(Accounts)]
@@ -41,10 +42,59 @@ pub struct LiquidateLoan<'info> {
     )]
     pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_QUEUE_SEED.as_bytes()],
+        bump,
+    )]
+    pub withdrawal_queue: Box<Account<'info, WithdrawalQueue>>,
+
+    /// Loans outstanding for the pool creator, created empty for loans opened before creator stats existed
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + CreatorStats::LEN,
+        seeds = [CREATOR_STATS_SEED.as_bytes(), pool_loan.user.as_ref()],
+        bump,
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
     pub cp_swap_program: Program<'info, RaydiumCpmm>,
-    /// Pays to mint the position
+    /// Treasury receiving the liquidation penalty
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED.as_bytes()],
+        bump,
+        constraint = treasury_vault.mint == service_vault.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Liquidator, anyone can liquidate an expired loan for the bounty
+    #[account(mut)]
     pub owner: Signer<'info>,
 
+    /// CHECK: Creator of the pool, receives what is left after the loan is settled
+    #[account(address = pool_loan.user)]
+    pub creator: UncheckedAccount<'info>,
+
+    /// The creator's token_0 account receiving the settlement
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = vault_0_mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_token_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The creator's token_1 account receiving the settlement
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = vault_1_mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_token_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
     /// CHECK: pool vault and lp mint authority
     #[account(
         seeds = [
@@ -127,6 +177,33 @@ pub struct LiquidateLoan<'info> {
         address = spl_memo::id()
     )]
     pub memo_program: UncheckedAccount<'info>,
+
+    /// Amm config of the pool, only needed to swap the tokens for sol
+    #[account(address = pool_state.load()?.amm_config)]
+    pub amm_config: Option<Box<Account<'info, AmmConfig>>>,
+
+    /// Oracle observations of the pool, the recovered sol is checked against the TWAP
+    #[account(
+        mut,
+        address = pool_state.load()?.observation_key
+    )]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    /// Expired auction of the loan, only needed when an auction nobody bid on has to be closed
+    #[account(
+        mut,
+        seeds = [LIQUIDATION_AUCTION_SEED.as_bytes(), pool_loan.key().as_ref()],
+        bump,
+        has_one = pool_loan,
+    )]
+    pub auction: Option<Box<Account<'info, LiquidationAuction>>>,
+
+    /// CHECK: Receives the rent of the expired auction
+    #[account(mut)]
+    pub auction_starter: Option<UncheckedAccount<'info>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn liquidate_loan(
@@ -134,26 +211,57 @@ pub fn liquidate_loan(
     lp_token_amount: u64,
     minimum_token_0_amount: u64,
     minimum_token_1_amount: u64,
+    minimum_sol_out: Option<u64>,
 ) -> Result<()> {
     let config = &mut ctx.accounts.config;
     let pool_loan = &mut ctx.accounts.pool_loan;
-    // Verify loan is not already repaid
-    require!(!pool_loan.is_repaid, ErrorCode::LoanAlreadyRepaid);
-    // Verify caller is either user or service
-    require!(
-        ctx.accounts.owner.key() == config.verifier || 
-        ctx.accounts.owner.key() == config.admin || 
-        ctx.accounts.owner.key() == config.syncer,
-        ErrorCode::Unauthorized
-    );
-
-    // Verify loan is not expired if called by user
+    config.require_not_paused(PAUSE_LIQUIDATIONS)?;
     let current_time = Clock::get()?.unix_timestamp;
+
+    // Liquidation always unwinds the whole escrowed position
     require!(
-        current_time > pool_loan.loan_start_time + pool_loan.loan_duration,
-        ErrorCode::LoanExpired
+        lp_token_amount > 0 && lp_token_amount == ctx.accounts.service_token_lp.amount,
+        ErrorCode::InvalidLpAmount
     );
 
+    let wrapped_sol_mint = Pubkey::from_str("So11111111111111111111111111111111111111112")
+        .map_err(|_| error!(ErrorCode::InvalidWrappedSolMint))?;
+
+    let is_token0_wrapped_sol = ctx.accounts.vault_0_mint.key() == wrapped_sol_mint;
+    let is_token1_wrapped_sol = ctx.accounts.vault_1_mint.key() == wrapped_sol_mint;
+
+    // Sol backing the escrowed lp at the pool TWAP, none while the pool has no usable observations
+    let escrowed_sol = lp_sol_value(
+        &*ctx.accounts.pool_state.load()?,
+        &*ctx.accounts.observation_state.load()?,
+        ctx.accounts.token_0_vault.amount,
+        ctx.accounts.token_1_vault.amount,
+        lp_token_amount,
+        is_token0_wrapped_sol,
+        current_time as u64,
+    ).ok();
+    if let Some(escrowed_sol) = escrowed_sol {
+        msg!("Escrowed lp is worth {} wrap sol at the pool TWAP", escrowed_sol);
+    }
+
+    let early_liquidation = pool_loan.check_liquidatable(
+        config,
+        escrowed_sol,
+        ctx.accounts.auction.as_deref().map(|auction| &**auction),
+        current_time,
+    )?;
+
+    // An auction nobody bid on only blocks liquidation until it expires, its rent goes back to the starter
+    if pool_loan.in_auction {
+        let auction = ctx.accounts.auction.as_ref().ok_or(ErrorCode::MissingAccount)?;
+        let auction_starter = ctx.accounts.auction_starter.as_ref().ok_or(ErrorCode::MissingAccount)?;
+        require_keys_eq!(auction_starter.key(), auction.starter, ErrorCode::InvalidRecipient);
+
+        auction.close(auction_starter.to_account_info())?;
+        pool_loan.in_auction = false;
+        msg!("Expired liquidation auction closed");
+    }
+
     // Define PDA authority seeds
     let (_vault_authority, vault_bump) = Pubkey::find_program_address(
         &[POOL_LOAN_SEED.as_bytes(), ctx.accounts.pool_state.key().as_ref()],
@@ -167,7 +275,7 @@ pub fn liquidate_loan(
     ];
 
     // Transfer LP tokens from service_token_lp to owner_lp_token account using PDA signer
-    let _= transfer_checked(
+    transfer_checked(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             TransferChecked {
@@ -180,14 +288,8 @@ pub fn liquidate_loan(
         ),
         lp_token_amount,
         ctx.accounts.lp_mint.decimals
-    );
-    msg!("Transferred {} LP tokens from service to user", lp_token_amount);
-
-    let wrapped_sol_mint = Pubkey::from_str("So11111111111111111111111111111111111111112")
-        .map_err(|_| error!(ErrorCode::InvalidWrappedSolMint))?;
-
-    let is_token0_wrapped_sol = ctx.accounts.vault_0_mint.key() == wrapped_sol_mint;
-    let is_token1_wrapped_sol = ctx.accounts.vault_1_mint.key() == wrapped_sol_mint;
+    )?;
+    msg!("Transferred {} LP tokens from service to liquidator", lp_token_amount);
 
     let mut pre_wrap_sol_amount = 0;
     let mut post_wrap_sol_amount = 0;
@@ -220,7 +322,7 @@ pub fn liquidate_loan(
         memo_program: ctx.accounts.memo_program.to_account_info(),
     };
     let cpi_context = CpiContext::new(ctx.accounts.cp_swap_program.to_account_info(), cpi_accounts);
-    let _= cpi::withdraw(cpi_context, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount);
+    cpi::withdraw(cpi_context, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount)?;
 
     ctx.accounts.token_0_account.reload()?;
     ctx.accounts.token_1_account.reload()?;
@@ -234,58 +336,187 @@ pub fn liquidate_loan(
     }
 
     let mut total_sol_received = post_wrap_sol_amount - pre_wrap_sol_amount;
-    let total_token_received = post_token_amount - pre_token_amount;
+    let mut total_token_received = post_token_amount - pre_token_amount;
     msg!("total_sol_received is {}", total_sol_received);
     msg!("total_token_received is {}", total_token_received);
-
-     let mut sol_profilt = 0;
-
-    if total_sol_received > pool_loan.init_sol_amount {
-        total_sol_received = pool_loan.init_sol_amount;
-        sol_profilt = total_sol_received - pool_loan.init_sol_amount;
-    }
-
-    // Send Wrapped Sol to the service vault after withdraw pool
-    if is_token0_wrapped_sol{
-        let _= transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.token_0_account.to_account_info(),
-                    to: ctx.accounts.service_vault.to_account_info(),
-                    authority: ctx.accounts.owner.to_account_info(),
-                    mint: ctx.accounts.vault_0_mint.to_account_info()
-                }
-            ),
-            total_sol_received,
-            ctx.accounts.vault_0_mint.decimals
-        );
-    } else {
-        let _= transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.token_1_account.to_account_info(),
-                    to: ctx.accounts.service_vault.to_account_info(),
-                    authority: ctx.accounts.owner.to_account_info(),
-                    mint: ctx.accounts.vault_1_mint.to_account_info()
-                }
-            ),
-            total_sol_received,
-            ctx.accounts.vault_1_mint.decimals
+    // An early liquidation rests on the TWAP so the withdrawn sol has to match it. Past the grace period
+    // the caller's minimum amounts bound the withdrawal, a pool without a usable TWAP can still be liquidated
+    if early_liquidation {
+        let escrowed_sol = escrowed_sol.ok_or(ErrorCode::OracleUnavailable)?;
+        require!(
+            total_sol_received >= config.min_liquidation_amount_out(escrowed_sol)?,
+            ErrorCode::LiquidationPriceDeviation
         );
     }
 
-    pool_loan.init_sol_amount = pool_loan.init_sol_amount.saturating_sub(total_sol_received + sol_profilt);
-    pool_loan.init_token_amount =  pool_loan.init_token_amount.saturating_sub(total_token_received);
+    pool_loan.accrue_interest(current_time)?;
+    pool_loan.charge_late_fee(current_time, config.late_fee_bps)?;
+
+    // Sell the tokens for sol when the withdrawn sol does not cover the debt, if the liquidator asked for it
+    let mut tokens_swapped = 0;
+    let mut sol_from_swap = 0;
+    if let Some(minimum_sol_out) = minimum_sol_out {
+        let sol_after_bounty = total_sol_received - config.liquidation_bounty(total_sol_received)?;
+        if sol_after_bounty < pool_loan.amount_owed() && total_token_received > 0 {
+            let amm_config = ctx.accounts.amm_config.as_ref().ok_or(ErrorCode::MissingAccount)?;
+
+            // The liquidator picks the minimum, it may not be looser than the TWAP quote less the slippage tolerance
+            ctx.accounts.token_0_vault.reload()?;
+            ctx.accounts.token_1_vault.reload()?;
+            let expected_sol_out = {
+                let pool_state = ctx.accounts.pool_state.load()?;
+                let (reserve_0, reserve_1) = pool_state.vault_amount_without_fee(
+                    ctx.accounts.token_0_vault.amount,
+                    ctx.accounts.token_1_vault.amount,
+                );
+                expected_swap_sol_out(
+                    reserve_0,
+                    reserve_1,
+                    twap_price_0_x32(
+                        &*ctx.accounts.observation_state.load()?,
+                        spot_price_0_x32(reserve_0, reserve_1)?,
+                        current_time as u64,
+                        TWAP_WINDOW,
+                    )?,
+                    is_token0_wrapped_sol,
+                    total_token_received,
+                    amm_config.trade_fee_rate,
+                )?
+            };
+            msg!("Swap is quoted at {} wrap sol at the pool TWAP", expected_sol_out);
+            require!(
+                minimum_sol_out >= config.min_liquidation_amount_out(expected_sol_out)?,
+                ErrorCode::LiquidationPriceDeviation
+            );
+
+            let (input_token_account, output_token_account, input_vault, output_vault, input_mint, output_mint) = if is_token0_wrapped_sol {
+                (
+                    &ctx.accounts.token_1_account,
+                    &ctx.accounts.token_0_account,
+                    &ctx.accounts.token_1_vault,
+                    &ctx.accounts.token_0_vault,
+                    &ctx.accounts.vault_1_mint,
+                    &ctx.accounts.vault_0_mint,
+                )
+            } else {
+                (
+                    &ctx.accounts.token_0_account,
+                    &ctx.accounts.token_1_account,
+                    &ctx.accounts.token_0_vault,
+                    &ctx.accounts.token_1_vault,
+                    &ctx.accounts.vault_0_mint,
+                    &ctx.accounts.vault_1_mint,
+                )
+            };
+            let input_token_program = if *input_mint.to_account_info().owner == ctx.accounts.token_program_2022.key() {
+                ctx.accounts.token_program_2022.to_account_info()
+            } else {
+                ctx.accounts.token_program.to_account_info()
+            };
+
+            let cpi_accounts = cpi::accounts::Swap {
+                payer: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+                amm_config: amm_config.to_account_info(),
+                pool_state: ctx.accounts.pool_state.to_account_info(),
+                input_token_account: input_token_account.to_account_info(),
+                output_token_account: output_token_account.to_account_info(),
+                input_vault: input_vault.to_account_info(),
+                output_vault: output_vault.to_account_info(),
+                input_token_program,
+                output_token_program: ctx.accounts.token_program.to_account_info(),
+                input_token_mint: input_mint.to_account_info(),
+                output_token_mint: output_mint.to_account_info(),
+                observation_state: ctx.accounts.observation_state.to_account_info(),
+            };
+            let cpi_context = CpiContext::new(ctx.accounts.cp_swap_program.to_account_info(), cpi_accounts);
+            cpi::swap_base_input(cpi_context, total_token_received, minimum_sol_out)?;
+
+            ctx.accounts.token_0_account.reload()?;
+            ctx.accounts.token_1_account.reload()?;
+            let (sol_balance, token_balance) = if is_token0_wrapped_sol {
+                (ctx.accounts.token_0_account.amount, ctx.accounts.token_1_account.amount)
+            } else {
+                (ctx.accounts.token_1_account.amount, ctx.accounts.token_0_account.amount)
+            };
+
+            sol_from_swap = sol_balance - post_wrap_sol_amount;
+            tokens_swapped = post_token_amount - token_balance;
+            total_sol_received += sol_from_swap;
+            total_token_received -= tokens_swapped;
+            msg!("Swapped {} tokens for {} wrap sol", tokens_swapped, sol_from_swap);
+        }
+    }
 
-    msg!("Updated pool loan: init_sol_amount={}, init_token_amount={}", pool_loan.init_sol_amount, pool_loan.init_token_amount);
-    msg!("{} tokens transferred to user", total_token_received);
+    // Settle the recovered sol: bounty to the liquidator, principal and interest to the vault,
+    // the penalty to the treasury out of the surplus and the rest to the creator
+    let bounty = config.liquidation_bounty(total_sol_received)?;
+    let repay_amount = (total_sol_received - bounty).min(pool_loan.amount_owed());
+    let surplus = total_sol_received - bounty - repay_amount;
+    let penalty = config.liquidation_penalty(total_sol_received)?.min(surplus);
+    let creator_sol_amount = surplus - penalty;
+    msg!(
+        "Liquidation settlement: bounty={} vault={} penalty={} creator={}",
+        bounty, repay_amount, penalty, creator_sol_amount
+    );
 
-    // Mark loan as repaid
-    pool_loan.is_repaid = true;
+    let (sol_account, sol_mint, creator_sol_account, token_account, token_mint, creator_token_account) = if is_token0_wrapped_sol {
+        (
+            &ctx.accounts.token_0_account,
+            &ctx.accounts.vault_0_mint,
+            &ctx.accounts.creator_token_0,
+            &ctx.accounts.token_1_account,
+            &ctx.accounts.vault_1_mint,
+            &ctx.accounts.creator_token_1,
+        )
+    } else {
+        (
+            &ctx.accounts.token_1_account,
+            &ctx.accounts.vault_1_mint,
+            &ctx.accounts.creator_token_1,
+            &ctx.accounts.token_0_account,
+            &ctx.accounts.vault_0_mint,
+            &ctx.accounts.creator_token_0,
+        )
+    };
+    let transfer_sol = |to, amount| {
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            sol_account.to_account_info(),
+            to,
+            ctx.accounts.owner.to_account_info(),
+            sol_mint.to_account_info(),
+            sol_mint.decimals,
+            amount,
+        )
+    };
 
-    config.amount += total_sol_received;
+    transfer_sol(ctx.accounts.service_vault.to_account_info(), repay_amount)?;
+    transfer_sol(ctx.accounts.treasury_vault.to_account_info(), penalty)?;
+    transfer_sol(creator_sol_account.to_account_info(), creator_sol_amount)?;
+
+    // The launched tokens belong to the creator
+    transfer_tokens(
+        ctx.accounts.token_program.to_account_info(),
+        token_account.to_account_info(),
+        creator_token_account.to_account_info(),
+        ctx.accounts.owner.to_account_info(),
+        token_mint.to_account_info(),
+        token_mint.decimals,
+        total_token_received,
+    )?;
+
+    pool_loan.init_token_amount =  pool_loan.init_token_amount.saturating_sub(total_token_received + tokens_swapped);
+    msg!("{} tokens transferred to creator", total_token_received);
+
+    settle_liquidation(
+        config,
+        pool_loan,
+        &mut ctx.accounts.creator_stats,
+        &mut ctx.accounts.withdrawal_queue,
+        repay_amount,
+        current_time,
+    );
 
     // Emit event for liquidation service logging
     emit!(LoanLiquidatedEvent {
@@ -293,9 +524,53 @@ pub fn liquidate_loan(
         user: pool_loan.user,
         liquidator: ctx.accounts.owner.key(),
         amount: lp_token_amount,
+        bounty,
+        penalty,
+        creator_sol_amount,
+        creator_token_amount: total_token_received,
+        tokens_swapped,
+        sol_from_swap,
+        shortfall: pool_loan.shortfall,
+        early_liquidation,
         timestamp: current_time,
     });
 
     Ok(())
     
-}
\ No newline at end of file
+}
+
+// Close a liquidated loan once `repay_amount` of the recovered sol reached the vault.
+// Principal left unrecovered is a realised loss for the vault and is recorded as bad debt
+pub fn settle_liquidation(
+    config: &mut Config,
+    pool_loan: &mut Account<PoolLoan>,
+    creator_stats: &mut CreatorStats,
+    withdrawal_queue: &mut WithdrawalQueue,
+    repay_amount: u64,
+    current_time: i64,
+) {
+    let (interest_paid, principal_paid) = pool_loan.apply_repayment(repay_amount);
+    pool_loan.shortfall = pool_loan.amount_owed();
+    pool_loan.is_repaid = true;
+    pool_loan.in_auction = false;
+    msg!("Updated pool loan: principal_outstanding={}, interest_accrued={}, init_token_amount={}", pool_loan.principal_outstanding, pool_loan.interest_accrued, pool_loan.init_token_amount);
+
+    config.record_loan_closed();
+    creator_stats.creator = pool_loan.user;
+    creator_stats.record_loan_closed();
+
+    config.record_repayment(interest_paid, principal_paid);
+    pool_loan.bad_debt = pool_loan.principal_outstanding;
+    config.record_loss(pool_loan.bad_debt);
+    if pool_loan.bad_debt > 0 {
+        msg!("Bad debt of {} wrap sol recorded", pool_loan.bad_debt);
+        emit!(BadDebtRecordedEvent {
+            pool: pool_loan.key(),
+            user: pool_loan.user,
+            amount: pool_loan.bad_debt,
+            bad_debt_outstanding: config.bad_debt_outstanding,
+            timestamp: current_time,
+        });
+    }
+    fill_withdrawal_queue(config, withdrawal_queue, current_time);
+}
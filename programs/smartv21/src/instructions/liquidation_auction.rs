@@ -0,0 +1,350 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::{transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount},
+};
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::{settle_liquidation, transfer_tokens} };
+
+// Put the escrowed lp of an expired loan up for a descending price auction
+#[derive(Accounts)]
+pub struct StartLiquidationAuction<'info> {
+    #[account(seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [POOL_LOAN_SEED.as_bytes(), pool_loan.pool.as_ref()],
+        bump,
+    )]
+    pub pool_loan: Box<Account<'info, PoolLoan>>,
+
+    #[account(
+        seeds = [LP_TOKEN_SEED.as_bytes(), pool_loan.pool.as_ref()],
+        bump,
+    )]
+    pub service_token_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = starter,
+        space = 8 + LiquidationAuction::LEN,
+        seeds = [LIQUIDATION_AUCTION_SEED.as_bytes(), pool_loan.key().as_ref()],
+        bump,
+    )]
+    pub auction: Box<Account<'info, LiquidationAuction>>,
+
+    /// Anyone can start the auction, the rent is refunded when it settles
+    #[account(mut)]
+    pub starter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BidLiquidationAuction<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [POOL_LOAN_SEED.as_bytes(), pool_loan.pool.as_ref()],
+        bump,
+    )]
+    pub pool_loan: Box<Account<'info, PoolLoan>>,
+
+    #[account(
+        mut,
+        seeds = [LIQUIDATION_AUCTION_SEED.as_bytes(), pool_loan.key().as_ref()],
+        bump,
+        has_one = pool_loan,
+        has_one = starter,
+        close = starter,
+    )]
+    pub auction: Box<Account<'info, LiquidationAuction>>,
+
+    /// CHECK: Receives the rent of the auction
+    #[account(mut)]
+    pub starter: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED.as_bytes(), pool_loan.pool.as_ref()],
+        bump,
+    )]
+    pub service_token_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury receiving the liquidation penalty
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED.as_bytes()],
+        bump,
+        constraint = treasury_vault.mint == service_vault.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_QUEUE_SEED.as_bytes()],
+        bump,
+    )]
+    pub withdrawal_queue: Box<Account<'info, WithdrawalQueue>>,
+
+    /// Loans outstanding for the pool creator, created empty for loans opened before creator stats existed
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        space = 8 + CreatorStats::LEN,
+        seeds = [CREATOR_STATS_SEED.as_bytes(), pool_loan.user.as_ref()],
+        bump,
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    /// The bidder's wrapped sol account paying the price
+    #[account(
+        mut,
+        token::mint = wsol_mint,
+        token::authority = bidder
+    )]
+    pub bidder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The bidder's account receiving the lp tokens
+    #[account(
+        mut,
+        token::mint = lp_mint,
+        token::authority = bidder
+    )]
+    pub bidder_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Creator of the pool, receives what is left after the loan is settled
+    #[account(address = pool_loan.user)]
+    pub creator: UncheckedAccount<'info>,
+
+    /// The creator's wrapped sol account receiving the settlement
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Wrapped sol mint
+    #[account(address = service_vault.mint)]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Pool lp token mint
+    #[account(address = pool_loan.lp_mint)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Close an auction nobody bid on once it expired, so the loan can be auctioned again or liquidated directly
+#[derive(Accounts)]
+pub struct CloseExpiredAuction<'info> {
+    #[account(
+        mut,
+        seeds = [POOL_LOAN_SEED.as_bytes(), pool_loan.pool.as_ref()],
+        bump,
+    )]
+    pub pool_loan: Box<Account<'info, PoolLoan>>,
+
+    #[account(
+        mut,
+        seeds = [LIQUIDATION_AUCTION_SEED.as_bytes(), pool_loan.key().as_ref()],
+        bump,
+        has_one = pool_loan,
+        has_one = starter,
+        close = starter,
+    )]
+    pub auction: Box<Account<'info, LiquidationAuction>>,
+
+    /// CHECK: Receives the rent of the auction
+    #[account(mut)]
+    pub starter: UncheckedAccount<'info>,
+}
+
+pub fn start_liquidation_auction(ctx: Context<StartLiquidationAuction>) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let pool_loan = &mut ctx.accounts.pool_loan;
+    config.require_not_paused(PAUSE_LIQUIDATIONS)?;
+    require!(
+        config.auction_decay_period > 0 && config.auction_max_duration > 0,
+        ErrorCode::AuctionsDisabled
+    );
+    require!(!pool_loan.is_repaid, ErrorCode::LoanAlreadyRepaid);
+    require!(ctx.accounts.service_token_lp.amount > 0, ErrorCode::InvalidLpAmount);
+
+    // Same eligibility as a direct liquidation
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(current_time > pool_loan.deadline(), ErrorCode::LoanNotExpired);
+    require!(
+        current_time > pool_loan.grace_end(config.grace_period),
+        ErrorCode::LoanInGracePeriod
+    );
+
+    // Prices are set relative to the debt at the start of the auction
+    pool_loan.accrue_interest(current_time)?;
+    pool_loan.charge_late_fee(current_time, config.late_fee_bps)?;
+    let amount_owed = pool_loan.amount_owed();
+
+    let auction = &mut ctx.accounts.auction;
+    auction.pool_loan = pool_loan.key();
+    auction.starter = ctx.accounts.starter.key();
+    auction.lp_amount = ctx.accounts.service_token_lp.amount;
+    auction.start_price = Config::bps_of(amount_owed, config.auction_start_price_bps)?;
+    auction.floor_price = Config::bps_of(amount_owed, config.auction_floor_price_bps)?;
+    auction.decay = config.auction_decay;
+    auction.decay_period = config.auction_decay_period;
+    auction.start_time = current_time;
+    auction.expires_at = current_time
+        .checked_add(config.auction_max_duration)
+        .ok_or(ErrorCode::MathOverflow)?;
+    pool_loan.in_auction = true;
+    msg!(
+        "Liquidation auction started for {} lp tokens, price {} to {} wrap sol",
+        auction.lp_amount, auction.start_price, auction.floor_price
+    );
+
+    emit!(LiquidationAuctionStartedEvent {
+        pool: pool_loan.key(),
+        user: pool_loan.user,
+        starter: auction.starter,
+        lp_amount: auction.lp_amount,
+        start_price: auction.start_price,
+        floor_price: auction.floor_price,
+        decay: auction.decay,
+        decay_period: auction.decay_period,
+        expires_at: auction.expires_at,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+// Buy the escrowed lp at the current auction price and settle the loan with the proceeds
+pub fn bid_liquidation_auction(ctx: Context<BidLiquidationAuction>, max_price: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let pool_loan = &mut ctx.accounts.pool_loan;
+    let auction = &ctx.accounts.auction;
+    config.require_not_paused(PAUSE_LIQUIDATIONS)?;
+    require!(!pool_loan.is_repaid, ErrorCode::LoanAlreadyRepaid);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(!auction.is_expired(current_time), ErrorCode::AuctionExpired);
+    let price = auction.current_price(current_time);
+    require!(price <= max_price, ErrorCode::AuctionPriceAboveMax);
+
+    // The price is the recovered sol: principal and interest to the vault,
+    // the penalty to the treasury out of the surplus and the rest to the creator
+    pool_loan.accrue_interest(current_time)?;
+    let repay_amount = price.min(pool_loan.amount_owed());
+    let surplus = price - repay_amount;
+    let penalty = config.liquidation_penalty(price)?.min(surplus);
+    let creator_sol_amount = surplus - penalty;
+    msg!(
+        "Auction settlement: price={} vault={} penalty={} creator={}",
+        price, repay_amount, penalty, creator_sol_amount
+    );
+
+    let transfer_sol = |to, amount| {
+        transfer_tokens(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.bidder_token_account.to_account_info(),
+            to,
+            ctx.accounts.bidder.to_account_info(),
+            ctx.accounts.wsol_mint.to_account_info(),
+            ctx.accounts.wsol_mint.decimals,
+            amount,
+        )
+    };
+
+    transfer_sol(ctx.accounts.service_vault.to_account_info(), repay_amount)?;
+    transfer_sol(ctx.accounts.treasury_vault.to_account_info(), penalty)?;
+    transfer_sol(ctx.accounts.creator_token_account.to_account_info(), creator_sol_amount)?;
+
+    // Release the escrowed lp to the bidder
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[POOL_LOAN_SEED.as_bytes(), pool_loan.pool.as_ref()],
+        ctx.program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[
+        POOL_LOAN_SEED.as_bytes(),
+        pool_loan.pool.as_ref(),
+        &[vault_bump],
+    ];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.service_token_lp.to_account_info(),
+                to: ctx.accounts.bidder_lp_token.to_account_info(),
+                authority: pool_loan.to_account_info(),
+                mint: ctx.accounts.lp_mint.to_account_info()
+            },
+            &[signer_seeds]
+        ),
+        auction.lp_amount,
+        ctx.accounts.lp_mint.decimals
+    )?;
+    msg!("Transferred {} LP tokens from service to bidder", auction.lp_amount);
+
+    settle_liquidation(
+        config,
+        pool_loan,
+        &mut ctx.accounts.creator_stats,
+        &mut ctx.accounts.withdrawal_queue,
+        repay_amount,
+        current_time,
+    );
+
+    emit!(LiquidationAuctionSettledEvent {
+        pool: pool_loan.key(),
+        user: pool_loan.user,
+        bidder: ctx.accounts.bidder.key(),
+        lp_amount: auction.lp_amount,
+        price,
+        penalty,
+        creator_sol_amount,
+        shortfall: pool_loan.shortfall,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+// Anyone can close an expired auction, the rent goes back to its starter
+pub fn close_expired_auction(ctx: Context<CloseExpiredAuction>) -> Result<()> {
+    let pool_loan = &mut ctx.accounts.pool_loan;
+    let auction = &ctx.accounts.auction;
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(auction.is_expired(current_time), ErrorCode::AuctionInProgress);
+
+    pool_loan.in_auction = false;
+    msg!("Expired liquidation auction closed");
+
+    emit!(LiquidationAuctionClosedEvent {
+        pool: pool_loan.key(),
+        user: pool_loan.user,
+        starter: auction.starter,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,279 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::{burn, mint_to, Burn, MintTo, Token},
+    token_2022::{transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount},
+};
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::* };
+
+// Create the share mint representing a pro-rata claim on the service vault
+#[derive(Accounts)]
+pub struct InitializeShareMint<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Wrapped sol mint
+    #[account(address = service_vault.mint)]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [SHARE_MINT_SEED.as_bytes()],
+        bump,
+        mint::decimals = wsol_mint.decimals,
+        mint::authority = config,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Mint shares for the vault assets deposited before the share mint existed
+#[derive(Accounts)]
+pub struct SeedShares<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED.as_bytes()],
+        bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The existing assets were deposited by the admin, their shares are protocol owned
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [PROTOCOL_SHARES_SEED.as_bytes()],
+        bump,
+        token::mint = share_mint,
+        token::authority = config,
+    )]
+    pub protocol_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageLiquidity<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Wrapped sol mint
+    #[account(address = service_vault.mint)]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED.as_bytes()],
+        bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The provider's wrapped sol account
+    #[account(
+        mut,
+        token::mint = wsol_mint,
+        token::authority = provider
+    )]
+    pub provider_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The provider's share account
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = provider
+    )]
+    pub provider_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn initialize_share_mint(ctx: Context<InitializeShareMint>) -> Result<()> {
+    msg!("Share mint {} initialized", ctx.accounts.share_mint.key());
+
+    Ok(())
+}
+
+pub fn seed_shares(ctx: Context<SeedShares>) -> Result<()> {
+    require!(ctx.accounts.share_mint.supply == 0, ErrorCode::SharesAlreadySeeded);
+
+    // Existing vault assets are priced 1:1, matching the first deposit into an empty vault
+    let shares = ctx.accounts.config.total_assets();
+    require!(shares > 0, ErrorCode::InvalidShareAmount);
+
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[CONFIG_SEED.as_bytes()],
+        ctx.program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
+
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.protocol_share_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            &[signer_seeds]
+        ),
+        shares,
+    )?;
+    msg!("Seeded {} shares for existing vault assets", shares);
+
+    Ok(())
+}
+
+// Deposit wrap sol into the service vault in exchange for shares
+pub fn deposit_liquidity(ctx: Context<ManageLiquidity>, amount: u64) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_DEPOSITS)?;
+    require!(amount > 0, ErrorCode::InvalidShareAmount);
+
+    let share_supply = ctx.accounts.share_mint.supply;
+    require!(
+        share_supply > 0 || ctx.accounts.config.total_assets() == 0,
+        ErrorCode::SharesNotSeeded
+    );
+
+    let shares = ctx.accounts.config.shares_for_assets(amount, share_supply)?;
+    require!(shares > 0, ErrorCode::InvalidShareAmount);
+
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.provider_token_account.to_account_info(),
+                to: ctx.accounts.service_vault.to_account_info(),
+                authority: ctx.accounts.provider.to_account_info(),
+                mint: ctx.accounts.wsol_mint.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.wsol_mint.decimals
+    )?;
+
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[CONFIG_SEED.as_bytes()],
+        ctx.program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
+
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.provider_share_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            &[signer_seeds]
+        ),
+        shares,
+    )?;
+
+    let config = &mut ctx.accounts.config;
+    config.record_deposit(amount);
+    msg!("Deposited {} wrap sol for {} shares", amount, shares);
+
+    emit!(LiquidityDepositedEvent {
+        provider: ctx.accounts.provider.key(),
+        amount,
+        shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Burn shares for their pro-rata part of the vault assets, limited by idle liquidity not owed to the withdrawal queue
+pub fn redeem(ctx: Context<ManageLiquidity>, shares: u64) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_VAULT_WITHDRAWALS)?;
+    require!(shares > 0, ErrorCode::InvalidShareAmount);
+
+    let amount = ctx.accounts.config.assets_for_shares(shares, ctx.accounts.share_mint.supply)?;
+    require!(amount > 0, ErrorCode::InvalidShareAmount);
+    require!(amount <= ctx.accounts.config.available_liquidity(), ErrorCode::InsufficientLiquidity);
+
+    burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                from: ctx.accounts.provider_share_account.to_account_info(),
+                authority: ctx.accounts.provider.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[CONFIG_SEED.as_bytes()],
+        ctx.program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.service_vault.to_account_info(),
+                to: ctx.accounts.provider_token_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+                mint: ctx.accounts.wsol_mint.to_account_info(),
+            },
+            &[signer_seeds]
+        ),
+        amount,
+        ctx.accounts.wsol_mint.decimals
+    )?;
+
+    let config = &mut ctx.accounts.config;
+    config.record_withdrawal(amount)?;
+    msg!("Redeemed {} shares for {} wrap sol", shares, amount);
+
+    emit!(LiquidityRedeemedEvent {
+        provider: ctx.accounts.provider.key(),
+        amount,
+        shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use raydium_cpmm_cpi::states::{ObservationState, PoolState};
+
+use crate::{ constants::*, state::*, error::ErrorCode, oracle::{lp_redeemable, lp_sol_value} };
+use std::str::FromStr;
+
+#[derive(Accounts)]
+pub struct GetLoanHealth<'info> {
+    #[account(seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [POOL_LOAN_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+    )]
+    pub pool_loan: Box<Account<'info, PoolLoan>>,
+
+    #[account(
+        seeds = [LP_TOKEN_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+    )]
+    pub service_token_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The address that holds pool tokens for token_0
+    #[account(
+        constraint = token_0_vault.key() == pool_state.load()?.token_0_vault
+    )]
+    pub token_0_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The address that holds pool tokens for token_1
+    #[account(
+        constraint = token_1_vault.key() == pool_state.load()?.token_1_vault
+    )]
+    pub token_1_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Oracle observations of the pool
+    #[account(address = pool_state.load()?.observation_key)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    /// Auction of the loan, only needed while one is running
+    #[account(
+        seeds = [LIQUIDATION_AUCTION_SEED.as_bytes(), pool_loan.key().as_ref()],
+        bump,
+    )]
+    pub auction: Option<Box<Account<'info, LiquidationAuction>>>,
+}
+
+// Read only view of a loan's collateral value, debt and liquidation status, meant to be simulated
+pub fn get_loan_health(ctx: Context<GetLoanHealth>) -> Result<LoanHealth> {
+    let config = &ctx.accounts.config;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let wrapped_sol_mint = Pubkey::from_str("So11111111111111111111111111111111111111112")
+        .map_err(|_| error!(ErrorCode::InvalidWrappedSolMint))?;
+    let is_token0_wrapped_sol = ctx.accounts.token_0_vault.mint == wrapped_sol_mint;
+
+    // Project the debt to now without writing the accrual back
+    let mut pool_loan = PoolLoan::clone(&ctx.accounts.pool_loan);
+    if !pool_loan.is_repaid {
+        pool_loan.accrue_interest(current_time)?;
+        pool_loan.charge_late_fee(current_time, config.late_fee_bps)?;
+    }
+    let amount_owed = if pool_loan.is_repaid { 0 } else { pool_loan.amount_owed() };
+
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let lp_amount = ctx.accounts.service_token_lp.amount;
+    let (token_0_redeemable, token_1_redeemable) = lp_redeemable(
+        &pool_state,
+        ctx.accounts.token_0_vault.amount,
+        ctx.accounts.token_1_vault.amount,
+        lp_amount,
+    )?;
+    let (sol_redeemable, token_redeemable) = if is_token0_wrapped_sol {
+        (token_0_redeemable, token_1_redeemable)
+    } else {
+        (token_1_redeemable, token_0_redeemable)
+    };
+
+    // A pool without enough observations yet has no TWAP and cannot be liquidated early
+    let twap_sol_value = lp_sol_value(
+        &pool_state,
+        &*ctx.accounts.observation_state.load()?,
+        ctx.accounts.token_0_vault.amount,
+        ctx.accounts.token_1_vault.amount,
+        lp_amount,
+        is_token0_wrapped_sol,
+        current_time as u64,
+    ).ok();
+
+    let health_ratio_bps = if amount_owed == 0 {
+        u64::MAX
+    } else {
+        u64::try_from(sol_redeemable as u128 * BPS_DENOMINATOR as u128 / amount_owed as u128)
+            .unwrap_or(u64::MAX)
+    };
+    // Same preconditions as liquidate_loan, which unwinds the whole escrowed position
+    let is_liquidatable = lp_amount > 0
+        && pool_loan.check_liquidatable(
+            config,
+            twap_sol_value,
+            ctx.accounts.auction.as_deref().map(|auction| &**auction),
+            current_time,
+        ).is_ok();
+
+    Ok(LoanHealth {
+        sol_redeemable,
+        token_redeemable,
+        twap_sol_value: twap_sol_value.unwrap_or(0),
+        amount_owed,
+        time_to_expiry: pool_loan.deadline().saturating_sub(current_time),
+        health_ratio_bps,
+        is_liquidatable,
+    })
+}
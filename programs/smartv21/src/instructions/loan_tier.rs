@@ -0,0 +1,153 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants::*, state::*, error::ErrorCode};
+
+#[derive(Accounts)]
+#[instruction(tier_id: u16)]
+pub struct AddLoanTier<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LoanTier::LEN,
+        seeds = [LOAN_TIER_SEED.as_bytes(), &tier_id.to_le_bytes()],
+        bump,
+    )]
+    pub loan_tier: Account<'info, LoanTier>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageLoanTier<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [LOAN_TIER_SEED.as_bytes(), &loan_tier.tier_id.to_le_bytes()],
+        bump,
+    )]
+    pub loan_tier: Account<'info, LoanTier>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetLoanDurations<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + LoanDurations::LEN,
+        seeds = [LOAN_DURATIONS_SEED.as_bytes()],
+        bump,
+    )]
+    pub loan_durations: Account<'info, LoanDurations>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Loan tiers are passed in remaining accounts
+#[derive(Accounts)]
+pub struct ListLoanTiers {}
+
+pub fn add_loan_tier(ctx: Context<AddLoanTier>, tier_id: u16, params: LoanTierParams) -> Result<()> {
+    ctx.accounts.config.require_no_timelock()?;
+    params.validate()?;
+
+    let loan_tier = &mut ctx.accounts.loan_tier;
+    loan_tier.tier_id = tier_id;
+    loan_tier.apply(&params);
+    loan_tier.is_enabled = true;
+
+    msg!("Loan tier {} added: {} lamports for {} to {} seconds", tier_id, loan_tier.sol_amount, loan_tier.min_duration, loan_tier.max_duration);
+
+    Ok(())
+}
+
+pub fn update_loan_tier(ctx: Context<ManageLoanTier>, params: LoanTierParams) -> Result<()> {
+    ctx.accounts.config.require_no_timelock()?;
+    params.validate()?;
+
+    let loan_tier = &mut ctx.accounts.loan_tier;
+    loan_tier.apply(&params);
+
+    msg!("Loan tier {} updated: {} lamports for {} to {} seconds", loan_tier.tier_id, loan_tier.sol_amount, loan_tier.min_duration, loan_tier.max_duration);
+
+    Ok(())
+}
+
+pub fn disable_loan_tier(ctx: Context<ManageLoanTier>) -> Result<()> {
+    ctx.accounts.config.require_no_timelock()?;
+
+    let loan_tier = &mut ctx.accounts.loan_tier;
+    loan_tier.is_enabled = false;
+
+    msg!("Loan tier {} disabled", loan_tier.tier_id);
+
+    Ok(())
+}
+
+pub fn enable_loan_tier(ctx: Context<ManageLoanTier>) -> Result<()> {
+    ctx.accounts.config.require_no_timelock()?;
+
+    let loan_tier = &mut ctx.accounts.loan_tier;
+    loan_tier.is_enabled = true;
+
+    msg!("Loan tier {} enabled", loan_tier.tier_id);
+
+    Ok(())
+}
+
+// Replace the loan durations creators can choose from
+pub fn set_loan_durations(ctx: Context<SetLoanDurations>, options: Vec<LoanDurationOption>) -> Result<()> {
+    ctx.accounts.config.require_no_timelock()?;
+    LoanDurations::validate_options(&options)?;
+
+    let loan_durations = &mut ctx.accounts.loan_durations;
+    loan_durations.options = options;
+
+    msg!("{} loan durations configured", loan_durations.options.len());
+
+    Ok(())
+}
+
+// Returns the loan tiers passed in remaining accounts, meant to be simulated by clients
+pub fn list_loan_tiers(ctx: Context<ListLoanTiers>) -> Result<Vec<LoanTier>> {
+    let mut loan_tiers = Vec::with_capacity(ctx.remaining_accounts.len());
+
+    for account in ctx.remaining_accounts.iter() {
+        require_keys_eq!(*account.owner, crate::ID, ErrorCode::InvalidLoanTier);
+        let loan_tier = LoanTier::try_deserialize(&mut &account.data.borrow()[..])
+            .map_err(|_| error!(ErrorCode::InvalidLoanTier))?;
+        loan_tiers.push(loan_tier);
+    }
+
+    Ok(loan_tiers)
+}
@@ -1,5 +1,8 @@
-use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::{
+    token::{burn, mint_to, Burn, MintTo},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
 
 use crate::{ constants::*, state::*, error::ErrorCode};
 
@@ -13,27 +16,109 @@ pub struct ManageServiceVault<'info> {
         mut,
         seeds = [CONFIG_SEED.as_bytes()],
         bump,
+        has_one = admin
     )]
-    pub config: Account<'info, Config>,
+    pub config: Box<Account<'info, Config>>,
 
     #[account(mut)]
-    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         seeds = [VAULT_SEED.as_bytes()],
         bump
     )]
-    pub service_vault: InterfaceAccount<'info, TokenAccount>,
+    pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(mut)]
-    pub admin_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub admin_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED.as_bytes()],
+        bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Shares of the protocol owned liquidity, held by the config
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [PROTOCOL_SHARES_SEED.as_bytes()],
+        bump,
+        token::mint = share_mint,
+        token::authority = config,
+    )]
+    pub protocol_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+// Grow a config account created by an older program version to the current layout
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: Deserialized manually, older layouts are shorter than Config::LEN
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
-// Deposit wrap sol into the service vault
+#[derive(Accounts)]
+pub struct MigratePoolLoan<'info> {
+    /// CHECK: Deserialized manually, older layouts are shorter than PoolLoan::LEN
+    #[account(
+        mut,
+        seeds = [POOL_LOAN_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub pool_loan: UncheckedAccount<'info>,
+
+    /// CHECK: Only used to derive the pool loan address
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// Anyone can migrate a loan, the payer covers the extra rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetVaultAccounting<'info> {
+    #[account(seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Deposit wrap sol into the service vault as protocol owned liquidity, priced in shares like any provider
 pub fn deposit(ctx: Context<ManageServiceVault>, amount: u64) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_DEPOSITS)?;
+    require!(amount > 0, ErrorCode::InvalidShareAmount);
+
+    let share_supply = ctx.accounts.share_mint.supply;
+    require!(
+        share_supply > 0 || ctx.accounts.config.total_assets() == 0,
+        ErrorCode::SharesNotSeeded
+    );
+    let shares = ctx.accounts.config.shares_for_assets(amount, share_supply)?;
+    require!(shares > 0, ErrorCode::InvalidShareAmount);
+
     // Transfer Wrap SOL tokens from user to service
     anchor_spl::token::transfer(
         CpiContext::new(
@@ -48,19 +133,40 @@ pub fn deposit(ctx: Context<ManageServiceVault>, amount: u64) -> Result<()> {
     )?;
     msg!("Transfer Wrap SOL tokens from user to service {}", amount);
 
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[CONFIG_SEED.as_bytes()],
+        ctx.program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
+
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.protocol_share_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            &[signer_seeds]
+        ),
+        shares,
+    )?;
+
     let config = &mut ctx.accounts.config;
-    config.amount += amount;
-    msg!("The wrap sol token amount of service vault is {}", config.amount);
+    config.record_deposit(amount);
+    msg!("Minted {} protocol shares, the wrap sol token amount of service vault is {}", shares, config.amount);
 
     Ok(())
 }
 
-// Withdraw wrap sol from service vault by owner anytime
+// Withdraw protocol owned liquidity from the service vault, burning the protocol shares backing it
 pub fn withdraw(ctx: Context<ManageServiceVault>, amount: u64) -> Result<()> {
     let accts = &ctx.accounts;
 
     // ✅ Authorization check
-    require!(accts.admin.key() == accts.config.admin, ErrorCode::Unauthorized);
+    require!(accts.config.admin_threshold == 0, ErrorCode::MultisigRequired);
+    accts.config.require_not_paused(PAUSE_VAULT_WITHDRAWALS)?;
+    require!(amount <= accts.config.available_liquidity(), ErrorCode::InsufficientBalance);
 
     // ✅ Prepare seeds for vault authority (PDA signer)
     let (_vault_authority, vault_bump) = Pubkey::find_program_address(
@@ -69,7 +175,17 @@ pub fn withdraw(ctx: Context<ManageServiceVault>, amount: u64) -> Result<()> {
     );
     let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
     let binding = [signer_seeds];
-    
+
+    let shares = burn_protocol_shares(
+        &accts.config,
+        &accts.share_mint,
+        &accts.protocol_share_account,
+        accts.token_program.to_account_info(),
+        signer_seeds,
+        amount,
+    )?;
+    msg!("Burned {} protocol shares", shares);
+
     // ✅ Transfer tokens from vault to admin
     let cpi_ctx = CpiContext::new_with_signer(
         accts.token_program.to_account_info(),
@@ -86,8 +202,161 @@ pub fn withdraw(ctx: Context<ManageServiceVault>, amount: u64) -> Result<()> {
 
     // ✅ Update vault amount
     let config = &mut ctx.accounts.config;
-    config.amount = config.amount.saturating_sub(amount);
+    // Large withdrawals have to be queued through the timelock
+    config.record_direct_withdrawal(amount, Clock::get()?.unix_timestamp)?;
+    config.record_withdrawal(amount)?;
     msg!("Updated vault balance: {}", config.amount);
 
     Ok(())
 }
+
+// Burn the protocol shares backing a withdrawal of amount from the service vault, returns the shares burned
+pub fn burn_protocol_shares<'info>(
+    config: &Account<'info, Config>,
+    share_mint: &InterfaceAccount<'info, Mint>,
+    protocol_share_account: &InterfaceAccount<'info, TokenAccount>,
+    token_program: AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    amount: u64,
+) -> Result<u64> {
+    let shares = config.shares_for_withdrawal(amount, share_mint.supply)?;
+    require!(shares <= protocol_share_account.amount, ErrorCode::InsufficientBalance);
+
+    burn(
+        CpiContext::new_with_signer(
+            token_program,
+            Burn {
+                mint: share_mint.to_account_info(),
+                from: protocol_share_account.to_account_info(),
+                authority: config.to_account_info(),
+            },
+            &[signer_seeds]
+        ),
+        shares,
+    )?;
+
+    Ok(shares)
+}
+
+
+// New fields are appended to Config, so zero filling the grown account gives them their default values.
+// principal_outstanding is reconciled off-chain from the open pool loans and only applied
+// the first time the accounting fields are populated.
+pub fn migrate_config(ctx: Context<MigrateConfig>, principal_outstanding: u64) -> Result<()> {
+    let config_info = ctx.accounts.config.to_account_info();
+
+    {
+        let data = config_info.try_borrow_data()?;
+        require!(data.len() >= 8 + 8 + 32, ErrorCode::InvalidConfig);
+        require!(data[..8] == Config::DISCRIMINATOR, ErrorCode::InvalidConfig);
+        // admin follows the discriminator and the amount
+        let admin = Pubkey::try_from(&data[16..48]).map_err(|_| error!(ErrorCode::InvalidConfig))?;
+        require_keys_eq!(admin, ctx.accounts.admin.key(), ErrorCode::Unauthorized);
+    }
+
+    let new_len = 8 + Config::LEN;
+    let old_len = config_info.data_len();
+    require!(old_len < new_len, ErrorCode::ConfigAlreadyMigrated);
+
+    let rent_due = Rent::get()?.minimum_balance(new_len).saturating_sub(config_info.lamports());
+    if rent_due > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: config_info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    config_info.realloc(new_len, true)?;
+
+    let mut config = Config::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+    if config.lifetime_lent == 0 && config.principal_outstanding == 0 {
+        config.principal_outstanding = principal_outstanding;
+        config.lifetime_lent = principal_outstanding;
+    }
+    // Zero is not a valid slippage, it is only left by the zero filling
+    if config.liquidation_slippage_bps == 0 {
+        config.liquidation_slippage_bps = DEFAULT_LIQUIDATION_SLIPPAGE_BPS;
+    }
+    config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+    msg!("Config migrated from {} to {} bytes", old_len, new_len);
+
+    Ok(())
+}
+
+// Grow a loan created before principal, interest and liquidation tracking to the current layout
+pub fn migrate_pool_loan(ctx: Context<MigratePoolLoan>) -> Result<()> {
+    let pool_loan_info = ctx.accounts.pool_loan.to_account_info();
+
+    {
+        let data = pool_loan_info.try_borrow_data()?;
+        require!(data.len() >= 8, ErrorCode::InvalidPoolLoan);
+        require!(data[..8] == PoolLoan::DISCRIMINATOR, ErrorCode::InvalidPoolLoan);
+    }
+
+    let new_len = 8 + PoolLoan::LEN;
+    let old_len = pool_loan_info.data_len();
+    require!(old_len < new_len, ErrorCode::PoolLoanAlreadyMigrated);
+
+    let rent_due = Rent::get()?.minimum_balance(new_len).saturating_sub(pool_loan_info.lamports());
+    if rent_due > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: pool_loan_info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    pool_loan_info.realloc(new_len, true)?;
+
+    // principal_outstanding follows the tier_id and extension_count fields, layouts
+    // ending before it predate principal tracking and have it zeroed
+    const PRINCIPAL_OFFSET: usize = 8 + 32 * 4 + 8 * 4 + 1 + 2 + 1;
+    let mut pool_loan = PoolLoan::try_deserialize(&mut &pool_loan_info.try_borrow_data()?[..])?;
+    if old_len <= PRINCIPAL_OFFSET {
+        // The interest rate stays zero, legacy loans keep the terms they were opened with
+        if !pool_loan.is_repaid {
+            pool_loan.principal_outstanding = pool_loan.init_sol_amount;
+        }
+        pool_loan.last_accrual_time = pool_loan.loan_start_time;
+    }
+    pool_loan.try_serialize(&mut &mut pool_loan_info.try_borrow_mut_data()?[..])?;
+
+    msg!("Pool loan migrated from {} to {} bytes", old_len, new_len);
+
+    Ok(())
+}
+
+// Returns the vault accounting, meant to be simulated by clients
+pub fn get_vault_accounting(ctx: Context<GetVaultAccounting>) -> Result<VaultAccounting> {
+    let config = &ctx.accounts.config;
+
+    Ok(VaultAccounting {
+        vault_balance: ctx.accounts.service_vault.amount,
+        idle_liquidity: config.amount,
+        principal_outstanding: config.principal_outstanding,
+        fees_accumulated: config.fees_accumulated,
+        interest_accumulated: config.interest_accumulated,
+        realized_losses: config.realized_losses,
+        total_deposited: config.total_deposited,
+        total_withdrawn: config.total_withdrawn,
+        lifetime_lent: config.lifetime_lent,
+        lifetime_repaid: config.lifetime_repaid,
+        loans_originated: config.loans_originated,
+        withdrawals_pending: config.withdrawals_pending,
+        withdrawals_reserved: config.withdrawals_reserved,
+        active_loans: config.active_loans,
+        window_lent: config.window_lent,
+        bad_debt_outstanding: config.bad_debt_outstanding,
+        bad_debt_total: config.bad_debt_total,
+    })
+}
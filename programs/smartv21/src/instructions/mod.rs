@@ -2,8 +2,30 @@ pub mod initialize;
 pub mod manage;
 pub mod withdraw_pool;
 pub mod liquidate_loan;
+pub mod loan_tier;
+pub mod extend_loan;
+pub mod repay_loan;
+pub mod fees;
+pub mod liquidity_provider;
+pub mod withdrawal_queue;
+pub mod admin;
+pub mod timelock;
+pub mod multisig;
+pub mod liquidation_auction;
+pub mod loan_health;
 
 pub use initialize::*;
 pub use manage::*;
 pub use withdraw_pool::*;
-pub use liquidate_loan::*;
\ No newline at end of file
+pub use liquidate_loan::*;
+pub use loan_tier::*;
+pub use extend_loan::*;
+pub use repay_loan::*;
+pub use fees::*;
+pub use liquidity_provider::*;
+pub use withdrawal_queue::*;
+pub use admin::*;
+pub use timelock::*;
+pub use multisig::*;
+pub use liquidation_auction::*;
+pub use loan_health::*;
\ No newline at end of file
@@ -0,0 +1,261 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::execute_admin_action };
+
+// Propose an admin action, executable once enough admin signers approve it
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        constraint = config.admin_threshold > 0 @ ErrorCode::InvalidAdminSigners,
+        constraint = config.is_admin_signer(&proposer.key()) @ ErrorCode::Unauthorized
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + AdminProposal::LEN,
+        seeds = [ADMIN_PROPOSAL_SEED.as_bytes(), &config.next_proposal_id.to_le_bytes()],
+        bump,
+    )]
+    pub proposal: Box<Account<'info, AdminProposal>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        constraint = config.is_admin_signer(&signer.key()) @ ErrorCode::Unauthorized
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [ADMIN_PROPOSAL_SEED.as_bytes(), &proposal.id.to_le_bytes()],
+        bump,
+    )]
+    pub proposal: Box<Account<'info, AdminProposal>>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [ADMIN_PROPOSAL_SEED.as_bytes(), &proposal.id.to_le_bytes()],
+        bump,
+        close = proposer,
+    )]
+    pub proposal: Box<Account<'info, AdminProposal>>,
+
+    /// CHECK: Receives the rent of the proposal
+    #[account(mut, address = proposal.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub executor: Signer<'info>,
+
+    /// Service vault, only needed for withdrawals
+    #[account(
+        mut,
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Treasury, only needed for treasury withdrawals
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED.as_bytes()],
+        bump
+    )]
+    pub treasury_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Token account receiving a withdrawal
+    #[account(mut)]
+    pub recipient_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Share mint, only needed for vault withdrawals
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED.as_bytes()],
+        bump
+    )]
+    pub share_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// Protocol shares burned for vault withdrawals
+    #[account(
+        mut,
+        seeds = [PROTOCOL_SHARES_SEED.as_bytes()],
+        bump
+    )]
+    pub protocol_share_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// Loan tier, only needed for tier updates
+    #[account(
+        mut,
+        seeds = [LOAN_TIER_SEED.as_bytes(), &loan_tier.tier_id.to_le_bytes()],
+        bump,
+    )]
+    pub loan_tier: Option<Box<Account<'info, LoanTier>>>,
+
+    /// Loan durations, only needed to replace the durations
+    #[account(
+        mut,
+        seeds = [LOAN_DURATIONS_SEED.as_bytes()],
+        bump,
+    )]
+    pub loan_durations: Option<Box<Account<'info, LoanDurations>>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        constraint = config.is_admin_signer(&authority.key()) || config.is_guardian(&authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [ADMIN_PROPOSAL_SEED.as_bytes(), &proposal.id.to_le_bytes()],
+        bump,
+        close = proposer,
+    )]
+    pub proposal: Box<Account<'info, AdminProposal>>,
+
+    /// CHECK: Receives the rent of the proposal
+    #[account(mut, address = proposal.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn create_proposal(ctx: Context<CreateProposal>, action: AdminAction) -> Result<()> {
+    action.validate()?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let config = &mut ctx.accounts.config;
+    let proposal = &mut ctx.accounts.proposal;
+
+    proposal.id = config.next_proposal_id;
+    proposal.proposer = ctx.accounts.proposer.key();
+    proposal.action = action;
+    proposal.approvals = [Pubkey::default(); MAX_ADMIN_SIGNERS];
+    proposal.created_at = current_time;
+    proposal.threshold_reached_at = 0;
+    config.next_proposal_id += 1;
+    msg!("Admin proposal {} created by {}", proposal.id, proposal.proposer);
+
+    emit!(ProposalCreatedEvent {
+        proposal_id: proposal.id,
+        proposer: proposal.proposer,
+        action,
+        timestamp: current_time,
+    });
+
+    // Creating a proposal counts as the proposer's approval
+    record_approval(config, proposal, ctx.accounts.proposer.key(), current_time)
+}
+
+pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+    record_approval(
+        &ctx.accounts.config,
+        &mut ctx.accounts.proposal,
+        ctx.accounts.signer.key(),
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let proposal = &ctx.accounts.proposal;
+    let config = &ctx.accounts.config;
+
+    // Signers may have changed since the approvals, only current signers count
+    require!(config.admin_threshold > 0, ErrorCode::InvalidAdminSigners);
+    require!(
+        proposal.threshold_reached_at > 0 && proposal.approval_count(config) >= config.admin_threshold as usize,
+        ErrorCode::ThresholdNotReached
+    );
+    require!(
+        current_time >= proposal.threshold_reached_at.saturating_add(config.timelock_delay),
+        ErrorCode::TimelockNotElapsed
+    );
+
+    let action = proposal.action;
+    execute_admin_action(
+        ctx.program_id,
+        &mut ctx.accounts.config,
+        &action,
+        ctx.accounts.service_vault.as_deref(),
+        ctx.accounts.treasury_vault.as_deref(),
+        ctx.accounts.recipient_token_account.as_deref(),
+        ctx.accounts.share_mint.as_deref(),
+        ctx.accounts.protocol_share_account.as_deref(),
+        ctx.accounts.token_program.as_ref(),
+        ctx.accounts.loan_tier.as_deref_mut(),
+        ctx.accounts.loan_durations.as_deref_mut(),
+        ctx.accounts.executor.key(),
+        current_time,
+    )?;
+    msg!("Admin proposal {} executed", proposal.id);
+
+    emit!(ProposalExecutedEvent {
+        proposal_id: proposal.id,
+        action,
+        executor: ctx.accounts.executor.key(),
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+    let proposal = &ctx.accounts.proposal;
+    msg!("Admin proposal {} cancelled by {}", proposal.id, ctx.accounts.authority.key());
+
+    emit!(ProposalCancelledEvent {
+        proposal_id: proposal.id,
+        cancelled_by: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+fn record_approval(config: &Config, proposal: &mut AdminProposal, signer: Pubkey, timestamp: i64) -> Result<()> {
+    proposal.approve(signer)?;
+
+    let approvals = proposal.approval_count(config);
+    if proposal.threshold_reached_at == 0 && approvals >= config.admin_threshold as usize {
+        proposal.threshold_reached_at = timestamp;
+    }
+    msg!("Admin proposal {} approved by {} ({} of {})", proposal.id, signer, approvals, config.admin_threshold);
+
+    emit!(ProposalApprovedEvent {
+        proposal_id: proposal.id,
+        signer,
+        approvals: approvals as u8,
+        threshold: config.admin_threshold,
+        timestamp,
+    });
+
+    Ok(())
+}
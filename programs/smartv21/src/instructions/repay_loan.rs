@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_2022::{transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount},
+};
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::fill_withdrawal_queue };
+
+#[derive(Accounts)]
+pub struct RepayWithSol<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [POOL_LOAN_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+        has_one = user @ ErrorCode::Unauthorized,
+        has_one = lp_mint,
+    )]
+    pub pool_loan: Box<Account<'info, PoolLoan>>,
+
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+    )]
+    pub service_token_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_QUEUE_SEED.as_bytes()],
+        bump,
+    )]
+    pub withdrawal_queue: Box<Account<'info, WithdrawalQueue>>,
+
+    /// Loans outstanding for the pool creator, created empty for loans opened before creator stats existed
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + CreatorStats::LEN,
+        seeds = [CREATOR_STATS_SEED.as_bytes(), pool_loan.user.as_ref()],
+        bump,
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
+    /// CHECK: Only used to derive the pool loan and escrow addresses
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// Creator of the pool, repays the loan
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The user's wrapped sol account repaying the loan
+    #[account(
+        mut,
+        token::mint = wsol_mint,
+        token::authority = user
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The user's lp token account receiving the escrowed LP tokens
+    #[account(
+        mut,
+        token::mint = lp_mint,
+        token::authority = user
+    )]
+    pub user_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool lp token mint
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Wrapped sol mint
+    #[account(address = service_vault.mint)]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Repay the loan from the user's own wrapped sol and keep the pool liquidity in place
+pub fn repay_with_sol(ctx: Context<RepayWithSol>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let pool_loan = &mut ctx.accounts.pool_loan;
+
+    config.require_not_paused(PAUSE_REPAYMENTS)?;
+    require!(!pool_loan.is_repaid, ErrorCode::LoanAlreadyRepaid);
+
+    // The creator can still repay during the grace period, paying the late fee
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        current_time <= pool_loan.grace_end(config.grace_period),
+        ErrorCode::LoanExpired
+    );
+
+    pool_loan.accrue_interest(current_time)?;
+    let late_fee = pool_loan.charge_late_fee(current_time, config.late_fee_bps)?;
+    let repay_amount = pool_loan.amount_owed();
+
+    // Transfer the outstanding principal and interest from the user to the service vault
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: ctx.accounts.service_vault.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+                mint: ctx.accounts.wsol_mint.to_account_info(),
+            },
+        ),
+        repay_amount,
+        ctx.accounts.wsol_mint.decimals
+    )?;
+    msg!("Repaid {} wrap sol to service vault", repay_amount);
+
+    let (interest_paid, principal_paid) = pool_loan.apply_repayment(repay_amount);
+    config.record_repayment(interest_paid, principal_paid);
+    pool_loan.is_repaid = true;
+    config.record_loan_closed();
+    ctx.accounts.creator_stats.creator = pool_loan.user;
+    ctx.accounts.creator_stats.record_loan_closed();
+    fill_withdrawal_queue(config, &mut ctx.accounts.withdrawal_queue, current_time);
+
+    // Define PDA authority seeds
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[POOL_LOAN_SEED.as_bytes(), ctx.accounts.pool_state.key().as_ref()],
+        ctx.program_id,
+    );
+    let binding = ctx.accounts.pool_state.key();
+    let signer_seeds: &[&[u8]] = &[
+        POOL_LOAN_SEED.as_bytes(),
+        binding.as_ref(),
+        &[vault_bump],
+    ];
+
+    // Release the escrowed LP tokens to the user
+    let lp_amount = ctx.accounts.service_token_lp.amount;
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.service_token_lp.to_account_info(),
+                to: ctx.accounts.user_lp_token.to_account_info(),
+                authority: pool_loan.to_account_info(),
+                mint: ctx.accounts.lp_mint.to_account_info()
+            },
+            &[signer_seeds]
+        ),
+        lp_amount,
+        ctx.accounts.lp_mint.decimals
+    )?;
+    msg!("Released {} LP tokens from service to user", lp_amount);
+
+    emit!(LoanRepaidEvent {
+        pool: pool_loan.pool,
+        user: pool_loan.user,
+        amount: repay_amount,
+        interest_paid,
+        late_fee,
+        principal_outstanding: pool_loan.principal_outstanding,
+        is_repaid: pool_loan.is_repaid,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
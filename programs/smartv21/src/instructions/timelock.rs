@@ -0,0 +1,481 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::burn_protocol_shares };
+
+// Queue an admin action executable by anyone once the timelock delay has passed
+#[derive(Accounts)]
+pub struct QueueAction<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PendingAction::LEN,
+        seeds = [PENDING_ACTION_SEED.as_bytes(), &config.next_action_id.to_le_bytes()],
+        bump,
+    )]
+    pub pending_action: Box<Account<'info, PendingAction>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAction<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_ACTION_SEED.as_bytes(), &pending_action.id.to_le_bytes()],
+        bump,
+        close = proposer,
+    )]
+    pub pending_action: Box<Account<'info, PendingAction>>,
+
+    /// CHECK: Receives the rent of the pending action
+    #[account(mut, address = pending_action.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub executor: Signer<'info>,
+
+    /// Service vault, only needed for withdrawals
+    #[account(
+        mut,
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Treasury, only needed for treasury withdrawals
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED.as_bytes()],
+        bump
+    )]
+    pub treasury_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Token account receiving a withdrawal
+    #[account(mut)]
+    pub recipient_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Share mint, only needed for vault withdrawals
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED.as_bytes()],
+        bump
+    )]
+    pub share_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// Protocol shares burned for vault withdrawals
+    #[account(
+        mut,
+        seeds = [PROTOCOL_SHARES_SEED.as_bytes()],
+        bump
+    )]
+    pub protocol_share_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// Loan tier, only needed for tier updates
+    #[account(
+        mut,
+        seeds = [LOAN_TIER_SEED.as_bytes(), &loan_tier.tier_id.to_le_bytes()],
+        bump,
+    )]
+    pub loan_tier: Option<Box<Account<'info, LoanTier>>>,
+
+    /// Loan durations, only needed to replace the durations
+    #[account(
+        mut,
+        seeds = [LOAN_DURATIONS_SEED.as_bytes()],
+        bump,
+    )]
+    pub loan_durations: Option<Box<Account<'info, LoanDurations>>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAction<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        constraint = config.admin == authority.key() || config.is_guardian(&authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_ACTION_SEED.as_bytes(), &pending_action.id.to_le_bytes()],
+        bump,
+        close = proposer,
+    )]
+    pub pending_action: Box<Account<'info, PendingAction>>,
+
+    /// CHECK: Receives the rent of the pending action
+    #[account(mut, address = pending_action.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn queue_action(ctx: Context<QueueAction>, action: AdminAction) -> Result<()> {
+    action.validate()?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let config = &mut ctx.accounts.config;
+    let pending_action = &mut ctx.accounts.pending_action;
+
+    pending_action.id = config.next_action_id;
+    pending_action.proposer = ctx.accounts.admin.key();
+    pending_action.action = action;
+    pending_action.queued_at = current_time;
+    pending_action.eta = current_time
+        .checked_add(config.timelock_delay)
+        .ok_or(ErrorCode::MathOverflow)?;
+    config.next_action_id += 1;
+    msg!("Admin action {} queued, executable from {}", pending_action.id, pending_action.eta);
+
+    emit!(ActionQueuedEvent {
+        action_id: pending_action.id,
+        action: pending_action.action,
+        eta: pending_action.eta,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+pub fn execute_action(ctx: Context<ExecuteAction>) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let pending_action = &ctx.accounts.pending_action;
+    require!(current_time >= pending_action.eta, ErrorCode::TimelockNotElapsed);
+
+    let action = pending_action.action;
+    execute_admin_action(
+        ctx.program_id,
+        &mut ctx.accounts.config,
+        &action,
+        ctx.accounts.service_vault.as_deref(),
+        ctx.accounts.treasury_vault.as_deref(),
+        ctx.accounts.recipient_token_account.as_deref(),
+        ctx.accounts.share_mint.as_deref(),
+        ctx.accounts.protocol_share_account.as_deref(),
+        ctx.accounts.token_program.as_ref(),
+        ctx.accounts.loan_tier.as_deref_mut(),
+        ctx.accounts.loan_durations.as_deref_mut(),
+        ctx.accounts.executor.key(),
+        current_time,
+    )?;
+
+    emit!(ActionExecutedEvent {
+        action_id: pending_action.id,
+        action,
+        executor: ctx.accounts.executor.key(),
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_action(ctx: Context<CancelAction>) -> Result<()> {
+    let pending_action = &ctx.accounts.pending_action;
+    msg!("Admin action {} cancelled by {}", pending_action.id, ctx.accounts.authority.key());
+
+    emit!(ActionCancelledEvent {
+        action_id: pending_action.id,
+        action: pending_action.action,
+        cancelled_by: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Run an approved admin action, withdrawals move tokens out of the program vaults
+// and loan tier changes write to the tier and durations accounts
+#[allow(clippy::too_many_arguments)]
+pub fn execute_admin_action<'info>(
+    program_id: &Pubkey,
+    config: &mut Account<'info, Config>,
+    action: &AdminAction,
+    service_vault: Option<&InterfaceAccount<'info, TokenAccount>>,
+    treasury_vault: Option<&InterfaceAccount<'info, TokenAccount>>,
+    recipient_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    share_mint: Option<&InterfaceAccount<'info, Mint>>,
+    protocol_share_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    token_program: Option<&Interface<'info, TokenInterface>>,
+    loan_tier: Option<&mut Account<'info, LoanTier>>,
+    loan_durations: Option<&mut Account<'info, LoanDurations>>,
+    authority: Pubkey,
+    timestamp: i64,
+) -> Result<()> {
+    let (from, amount, recipient) = match *action {
+        AdminAction::UpdateLoanTier { tier_id, params } => {
+            action.validate()?;
+            let loan_tier = loan_tier.ok_or(ErrorCode::MissingAccount)?;
+            require!(loan_tier.tier_id == tier_id, ErrorCode::InvalidLoanTier);
+            loan_tier.apply(&params);
+            msg!("Loan tier {} updated: {} lamports for {} to {} seconds", tier_id, loan_tier.sol_amount, loan_tier.min_duration, loan_tier.max_duration);
+            return Ok(());
+        }
+        AdminAction::SetLoanTierEnabled { tier_id, is_enabled } => {
+            let loan_tier = loan_tier.ok_or(ErrorCode::MissingAccount)?;
+            require!(loan_tier.tier_id == tier_id, ErrorCode::InvalidLoanTier);
+            loan_tier.is_enabled = is_enabled;
+            msg!("Loan tier {} {}", tier_id, if is_enabled { "enabled" } else { "disabled" });
+            return Ok(());
+        }
+        AdminAction::SetLoanDurations { options, count } => {
+            action.validate()?;
+            let loan_durations = loan_durations.ok_or(ErrorCode::MissingAccount)?;
+            loan_durations.options = options[..count as usize].to_vec();
+            msg!("{} loan durations configured", loan_durations.options.len());
+            return Ok(());
+        }
+        AdminAction::Withdraw { amount, recipient } => {
+            (service_vault.ok_or(ErrorCode::MissingAccount)?, amount, recipient)
+        }
+        AdminAction::WithdrawTreasury { amount, recipient } => {
+            (treasury_vault.ok_or(ErrorCode::MissingAccount)?, amount, recipient)
+        }
+        _ => return apply_config_action(config, action, authority, timestamp),
+    };
+    let recipient_token_account = recipient_token_account.ok_or(ErrorCode::MissingAccount)?;
+    let token_program = token_program.ok_or(ErrorCode::MissingAccount)?;
+    require_keys_eq!(recipient_token_account.key(), recipient, ErrorCode::InvalidRecipient);
+    require!(amount <= from.amount, ErrorCode::InsufficientBalance);
+
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[CONFIG_SEED.as_bytes()],
+        program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
+
+    // Vault withdrawals only take protocol owned liquidity, never the providers' assets
+    if let AdminAction::Withdraw { .. } = action {
+        let shares = burn_protocol_shares(
+            config,
+            share_mint.ok_or(ErrorCode::MissingAccount)?,
+            protocol_share_account.ok_or(ErrorCode::MissingAccount)?,
+            token_program.to_account_info(),
+            signer_seeds,
+            amount,
+        )?;
+        msg!("Burned {} protocol shares", shares);
+    }
+
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: from.to_account_info(),
+                to: recipient_token_account.to_account_info(),
+                authority: config.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        amount,
+    )?;
+
+    // Only the service vault is part of the vault accounting
+    if let AdminAction::Withdraw { .. } = action {
+        config.record_withdrawal(amount)?;
+        msg!("Withdraw {} tokens from service vault to {}", amount, recipient);
+    } else {
+        msg!("Withdraw {} tokens from treasury to {}", amount, recipient);
+
+        emit!(TreasuryWithdrawnEvent {
+            authority,
+            recipient,
+            amount,
+            timestamp,
+        });
+    }
+
+    Ok(())
+}
+
+// Apply an admin action that only touches the config, shared by the direct setters, the timelock and the multisig
+pub fn apply_config_action(config: &mut Config, action: &AdminAction, authority: Pubkey, timestamp: i64) -> Result<()> {
+    action.validate()?;
+
+    match *action {
+        AdminAction::UpdateFeeSchedule { fixed_fee, fee_bps, min_fee, max_fee } => {
+            config.service_fee = fixed_fee;
+            config.service_fee_bps = fee_bps;
+            config.min_service_fee = min_fee;
+            config.max_service_fee = max_fee;
+            msg!("Fee schedule updated: fixed={} bps={} min={} max={}", fixed_fee, fee_bps, min_fee, max_fee);
+        }
+        AdminAction::UpdateFeeSplit { treasury_fee_bps, insurance_fee_bps, referrer_fee_bps } => {
+            config.treasury_fee_bps = treasury_fee_bps;
+            config.insurance_fee_bps = insurance_fee_bps;
+            config.referrer_fee_bps = referrer_fee_bps;
+            msg!("Fee split updated: treasury={} insurance={} referrer={}", treasury_fee_bps, insurance_fee_bps, referrer_fee_bps);
+        }
+        AdminAction::UpdateInterestRate { interest_rate_bps } => {
+            // Applies to loans created from now on, existing loans keep their rate
+            config.interest_rate_bps = interest_rate_bps;
+            msg!("Interest rate updated to {} bps per year", interest_rate_bps);
+        }
+        AdminAction::UpdateLendingCaps {
+            max_utilization_bps,
+            max_active_loans,
+            max_loans_per_creator,
+            max_lent_per_window,
+            lending_window,
+        } => {
+            config.max_utilization_bps = max_utilization_bps;
+            config.max_active_loans = max_active_loans;
+            config.max_loans_per_creator = max_loans_per_creator;
+            config.max_lent_per_window = max_lent_per_window;
+            config.lending_window = lending_window;
+            msg!(
+                "Lending caps updated: utilization={} bps active={} per_creator={} window={} per {}s",
+                max_utilization_bps, max_active_loans, max_loans_per_creator, max_lent_per_window, lending_window
+            );
+        }
+        AdminAction::ProposeAdmin { new_admin } => {
+            config.pending_admin = new_admin;
+            msg!("Admin transfer proposed from {} to {}", config.admin, new_admin);
+
+            emit!(AdminProposedEvent {
+                admin: config.admin,
+                pending_admin: new_admin,
+                timestamp,
+            });
+        }
+        AdminAction::SetSyncer { syncer } => {
+            let previous = config.syncer;
+            config.syncer = syncer;
+            msg!("Syncer updated from {} to {}", previous, syncer);
+
+            emit!(RoleUpdatedEvent {
+                role: Role::Syncer,
+                previous,
+                new: syncer,
+                timestamp,
+            });
+        }
+        AdminAction::SetVerifier { verifier } => {
+            let previous = config.verifier;
+            config.verifier = verifier;
+            msg!("Verifier updated from {} to {}", previous, verifier);
+
+            emit!(RoleUpdatedEvent {
+                role: Role::Verifier,
+                previous,
+                new: verifier,
+                timestamp,
+            });
+        }
+        AdminAction::SetTimelock { delay, large_withdrawal_threshold } => {
+            config.timelock_delay = delay;
+            config.large_withdrawal_threshold = large_withdrawal_threshold;
+            msg!("Timelock updated: delay={}s large_withdrawal_threshold={}", delay, large_withdrawal_threshold);
+        }
+        AdminAction::AddGuardian { guardian } => {
+            require!(!config.is_guardian(&guardian), ErrorCode::GuardianAlreadyExists);
+
+            let slot = config.guardians
+                .iter_mut()
+                .find(|key| **key == Pubkey::default())
+                .ok_or(ErrorCode::MaxGuardiansReached)?;
+            *slot = guardian;
+            msg!("Guardian {} added", guardian);
+
+            emit!(GuardianUpdatedEvent {
+                guardian,
+                is_added: true,
+                timestamp,
+            });
+        }
+        AdminAction::RemoveGuardian { guardian } => {
+            let slot = config.guardians
+                .iter_mut()
+                .find(|key| **key == guardian)
+                .ok_or(ErrorCode::GuardianNotFound)?;
+            *slot = Pubkey::default();
+            msg!("Guardian {} removed", guardian);
+
+            emit!(GuardianUpdatedEvent {
+                guardian,
+                is_added: false,
+                timestamp,
+            });
+        }
+        AdminAction::Unpause { operations } => {
+            let paused_operations = config.paused_operations & !operations;
+            config.set_paused_operations(paused_operations)?;
+            msg!("Unpaused operations {:#07b}, now paused {:#07b}", operations, config.paused_operations);
+
+            emit!(PauseUpdatedEvent {
+                authority,
+                paused_operations: config.paused_operations,
+                timestamp,
+            });
+        }
+        AdminAction::SetAdminSigners { signers, threshold } => {
+            config.admin_signers = signers;
+            config.admin_threshold = threshold;
+            msg!("Admin signers updated, threshold {} of {}", threshold, config.admin_signer_count());
+
+            emit!(AdminSignersUpdatedEvent {
+                signers,
+                threshold,
+                timestamp,
+            });
+        }
+        AdminAction::UpdateLiquidationBounty { bounty_bps } => {
+            config.liquidation_bounty_bps = bounty_bps;
+            msg!("Liquidation bounty updated to {} bps", bounty_bps);
+        }
+        AdminAction::UpdateLiquidationPenalty { penalty_bps } => {
+            config.liquidation_penalty_bps = penalty_bps;
+            msg!("Liquidation penalty updated to {} bps", penalty_bps);
+        }
+        AdminAction::UpdateAuctionParams { decay, decay_period, start_price_bps, floor_price_bps, max_duration } => {
+            config.auction_decay = decay;
+            config.auction_decay_period = decay_period;
+            config.auction_start_price_bps = start_price_bps;
+            config.auction_floor_price_bps = floor_price_bps;
+            config.auction_max_duration = max_duration;
+            msg!(
+                "Auction params updated: decay_period={}s start={} bps floor={} bps max_duration={}s",
+                decay_period, start_price_bps, floor_price_bps, max_duration
+            );
+        }
+        AdminAction::UpdateHealthThreshold { health_threshold_bps } => {
+            config.health_threshold_bps = health_threshold_bps;
+            msg!("Health threshold updated to {} bps", health_threshold_bps);
+        }
+        AdminAction::UpdateLiquidationSlippage { slippage_bps } => {
+            config.liquidation_slippage_bps = slippage_bps;
+            msg!("Liquidation slippage updated to {} bps", slippage_bps);
+        }
+        AdminAction::UpdateGracePeriod { grace_period, late_fee_bps } => {
+            config.grace_period = grace_period;
+            config.late_fee_bps = late_fee_bps;
+            msg!("Grace period updated to {}s with a {} bps late fee", grace_period, late_fee_bps);
+        }
+        AdminAction::Withdraw { .. }
+        | AdminAction::WithdrawTreasury { .. }
+        | AdminAction::UpdateLoanTier { .. }
+        | AdminAction::SetLoanTierEnabled { .. }
+        | AdminAction::SetLoanDurations { .. } => {
+            return err!(ErrorCode::InvalidAdminAction)
+        }
+    }
+
+    Ok(())
+}
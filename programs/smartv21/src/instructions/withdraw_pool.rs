@@ -11,7 +11,7 @@ use raydium_cpmm_cpi::{
 };
 
 
-use crate::{ constants::*, state::*, error::ErrorCode};
+use crate::{ constants::*, state::*, error::ErrorCode, event::*, instructions::fill_withdrawal_queue };
 use std::str::FromStr;
 
 #[derive(Accounts)]
@@ -41,8 +41,26 @@ pub struct RemoveLiquidity<'info> {
     )]
     pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_QUEUE_SEED.as_bytes()],
+        bump,
+    )]
+    pub withdrawal_queue: Box<Account<'info, WithdrawalQueue>>,
+
+    /// Loans outstanding for the pool creator, created empty for loans opened before creator stats existed
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + CreatorStats::LEN,
+        seeds = [CREATOR_STATS_SEED.as_bytes(), pool_loan.user.as_ref()],
+        bump,
+    )]
+    pub creator_stats: Box<Account<'info, CreatorStats>>,
+
     pub cp_swap_program: Program<'info, RaydiumCpmm>,
     /// Pays to mint the position
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     /// CHECK: pool vault and lp mint authority
@@ -127,6 +145,8 @@ pub struct RemoveLiquidity<'info> {
         address = spl_memo::id()
     )]
     pub memo_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn remove_liquidity(
@@ -137,6 +157,7 @@ pub fn remove_liquidity(
 ) -> Result<()> {
     let config = &mut ctx.accounts.config;
     let pool_loan = &mut ctx.accounts.pool_loan;
+    config.require_not_paused(PAUSE_REPAYMENTS)?;
     // Verify loan is not already repaid
     require!(!pool_loan.is_repaid, ErrorCode::LoanAlreadyRepaid);
     // Verify caller is either user or service
@@ -147,15 +168,20 @@ pub fn remove_liquidity(
         ErrorCode::Unauthorized
     );
 
-    // Verify loan is not expired if called by user
+    // Verify loan is not past its grace period
     let current_time = Clock::get()?.unix_timestamp;
     // if ctx.accounts.owner.key() == pool_loan.user {
     require!(
-        current_time <= pool_loan.loan_start_time + pool_loan.loan_duration,
+        current_time <= pool_loan.grace_end(config.grace_period),
         ErrorCode::LoanExpired
     );
     // }
 
+    require!(
+        lp_token_amount > 0 && lp_token_amount <= ctx.accounts.service_token_lp.amount,
+        ErrorCode::InvalidLpAmount
+    );
+
     // Define PDA authority seeds
     let (_vault_authority, vault_bump) = Pubkey::find_program_address(
         &[POOL_LOAN_SEED.as_bytes(), ctx.accounts.pool_state.key().as_ref()],
@@ -169,7 +195,7 @@ pub fn remove_liquidity(
     ];
 
     // Transfer LP tokens from service_token_lp to owner_lp_token account using PDA signer
-    let _= transfer_checked(
+    transfer_checked(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             TransferChecked {
@@ -182,7 +208,7 @@ pub fn remove_liquidity(
         ),
         lp_token_amount,
         ctx.accounts.lp_mint.decimals
-    );
+    )?;
     msg!("Transferred {} LP tokens from service to user", lp_token_amount);
 
     let wrapped_sol_mint = Pubkey::from_str("So11111111111111111111111111111111111111112")
@@ -221,7 +247,7 @@ pub fn remove_liquidity(
         memo_program: ctx.accounts.memo_program.to_account_info(),
     };
     let cpi_context = CpiContext::new(ctx.accounts.cp_swap_program.to_account_info(), cpi_accounts);
-    let _= cpi::withdraw(cpi_context, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount);
+    cpi::withdraw(cpi_context, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount)?;
 
     ctx.accounts.token_0_account.reload()?;
     ctx.accounts.token_1_account.reload()?;
@@ -234,58 +260,96 @@ pub fn remove_liquidity(
         post_token_amount = ctx.accounts.token_0_account.amount;
     }
 
-    let mut total_sol_received = post_wrap_sol_amount - pre_wrap_sol_amount;
+    let total_sol_received = post_wrap_sol_amount - pre_wrap_sol_amount;
     let total_token_received = post_token_amount - pre_token_amount;
     msg!("total_sol_received is {}", total_sol_received);
     msg!("total_token_received is {}", total_token_received);
 
-    let mut sol_profilt = 0;
-
-    if total_sol_received > pool_loan.init_sol_amount {
-        total_sol_received = pool_loan.init_sol_amount;
-        sol_profilt = total_sol_received - pool_loan.init_sol_amount;
-    }
+    // Repay as much of the interest and principal as the withdrawn sol covers, the rest stays with the user
+    pool_loan.accrue_interest(current_time)?;
+    let late_fee = pool_loan.charge_late_fee(current_time, config.late_fee_bps)?;
+    let repay_amount = total_sol_received.min(pool_loan.amount_owed());
 
     // Send Wrapped Sol to the service vault after withdraw pool
-    if is_token0_wrapped_sol{
-        let _= transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.token_0_account.to_account_info(),
-                    to: ctx.accounts.service_vault.to_account_info(),
-                    authority: ctx.accounts.owner.to_account_info(),
-                    mint: ctx.accounts.vault_0_mint.to_account_info()
-                }
-            ),
-            total_sol_received,
-            ctx.accounts.vault_0_mint.decimals
-        );
-    } else {
-        let _= transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.token_1_account.to_account_info(),
-                    to: ctx.accounts.service_vault.to_account_info(),
-                    authority: ctx.accounts.owner.to_account_info(),
-                    mint: ctx.accounts.vault_1_mint.to_account_info()
-                }
-            ),
-            total_sol_received,
-            ctx.accounts.vault_1_mint.decimals
-        );
+    if repay_amount > 0 {
+        if is_token0_wrapped_sol {
+            transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.token_0_account.to_account_info(),
+                        to: ctx.accounts.service_vault.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                        mint: ctx.accounts.vault_0_mint.to_account_info()
+                    }
+                ),
+                repay_amount,
+                ctx.accounts.vault_0_mint.decimals
+            )?;
+        } else {
+            transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.token_1_account.to_account_info(),
+                        to: ctx.accounts.service_vault.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                        mint: ctx.accounts.vault_1_mint.to_account_info()
+                    }
+                ),
+                repay_amount,
+                ctx.accounts.vault_1_mint.decimals
+            )?;
+        }
     }
 
-    pool_loan.init_sol_amount = pool_loan.init_sol_amount.saturating_sub(total_sol_received + sol_profilt);
+    let (interest_paid, principal_paid) = pool_loan.apply_repayment(repay_amount);
     pool_loan.init_token_amount =  pool_loan.init_token_amount.saturating_sub(total_token_received);
 
-    msg!("Updated pool loan: init_sol_amount={}, init_token_amount={}", pool_loan.init_sol_amount, pool_loan.init_token_amount);
+    msg!("Updated pool loan: principal_outstanding={}, interest_accrued={}, init_token_amount={}", pool_loan.principal_outstanding, pool_loan.interest_accrued, pool_loan.init_token_amount);
     msg!("{} tokens transferred to user", total_token_received);
 
-    pool_loan.is_repaid = true;
+    config.record_repayment(interest_paid, principal_paid);
+    fill_withdrawal_queue(config, &mut ctx.accounts.withdrawal_queue, current_time);
+
+    // Close the loan once principal and interest are repaid and release the LP tokens left in escrow
+    if pool_loan.amount_owed() == 0 {
+        pool_loan.is_repaid = true;
+        config.record_loan_closed();
+        ctx.accounts.creator_stats.creator = pool_loan.user;
+        ctx.accounts.creator_stats.record_loan_closed();
+
+        ctx.accounts.service_token_lp.reload()?;
+        let remaining_lp_amount = ctx.accounts.service_token_lp.amount;
+        if remaining_lp_amount > 0 {
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.service_token_lp.to_account_info(),
+                        to: ctx.accounts.owner_lp_token.to_account_info(),
+                        authority: pool_loan.to_account_info(),
+                        mint: ctx.accounts.lp_mint.to_account_info()
+                    },
+                    &[signer_seeds]
+                ),
+                remaining_lp_amount,
+                ctx.accounts.lp_mint.decimals
+            )?;
+            msg!("Released {} remaining LP tokens to user", remaining_lp_amount);
+        }
+    }
 
-    config.amount += total_sol_received;
+    emit!(LoanRepaidEvent {
+        pool: pool_loan.pool,
+        user: pool_loan.user,
+        amount: repay_amount,
+        interest_paid,
+        late_fee,
+        principal_outstanding: pool_loan.principal_outstanding,
+        is_repaid: pool_loan.is_repaid,
+        timestamp: current_time,
+    });
 
     Ok(())
     
@@ -0,0 +1,259 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::{burn, Burn, Token},
+    token_2022::{transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount},
+};
+
+use crate::{ constants::*, state::*, error::ErrorCode, event::* };
+
+#[derive(Accounts)]
+pub struct InitializeWithdrawalQueue<'info> {
+    #[account(
+        seeds = [CONFIG_SEED.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = config.admin_threshold == 0 @ ErrorCode::MultisigRequired
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + WithdrawalQueue::LEN,
+        seeds = [WITHDRAWAL_QUEUE_SEED.as_bytes()],
+        bump,
+    )]
+    pub withdrawal_queue: Box<Account<'info, WithdrawalQueue>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_QUEUE_SEED.as_bytes()],
+        bump,
+    )]
+    pub withdrawal_queue: Box<Account<'info, WithdrawalQueue>>,
+
+    /// Receipt of the request, claimed once the request leaves the queue
+    #[account(
+        init,
+        payer = provider,
+        space = 8 + WithdrawalTicket::LEN,
+        seeds = [WITHDRAWAL_TICKET_SEED.as_bytes(), &withdrawal_queue.next_request_id.to_le_bytes()],
+        bump,
+    )]
+    pub withdrawal_ticket: Box<Account<'info, WithdrawalTicket>>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED.as_bytes()],
+        bump
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The provider's share account
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = provider
+    )]
+    pub provider_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(request_id: u64)]
+pub struct ClaimWithdrawal<'info> {
+    #[account(mut, seeds = [CONFIG_SEED.as_bytes()], bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [WITHDRAWAL_QUEUE_SEED.as_bytes()],
+        bump,
+    )]
+    pub withdrawal_queue: Box<Account<'info, WithdrawalQueue>>,
+
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_TICKET_SEED.as_bytes(), &request_id.to_le_bytes()],
+        bump,
+        constraint = withdrawal_ticket.owner == provider.key() @ ErrorCode::Unauthorized,
+        close = provider,
+    )]
+    pub withdrawal_ticket: Box<Account<'info, WithdrawalTicket>>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED.as_bytes()],
+        bump
+    )]
+    pub service_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Wrapped sol mint
+    #[account(address = service_vault.mint)]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The provider's wrapped sol account
+    #[account(
+        mut,
+        token::mint = wsol_mint,
+        token::authority = provider
+    )]
+    pub provider_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn initialize_withdrawal_queue(ctx: Context<InitializeWithdrawalQueue>) -> Result<()> {
+    let withdrawal_queue = &mut ctx.accounts.withdrawal_queue;
+    withdrawal_queue.next_request_id = 0;
+    withdrawal_queue.requests = Vec::new();
+
+    Ok(())
+}
+
+// Burn shares for wrap sol paid out once the vault has enough idle liquidity
+pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_VAULT_WITHDRAWALS)?;
+    require!(shares > 0, ErrorCode::InvalidShareAmount);
+    require!(
+        ctx.accounts.withdrawal_queue.requests.len() < MAX_WITHDRAWAL_REQUESTS,
+        ErrorCode::WithdrawalQueueFull
+    );
+
+    // The request is priced now, the provider stops sharing in vault revenue and losses
+    let amount = ctx.accounts.config.assets_for_shares(shares, ctx.accounts.share_mint.supply)?;
+    require!(amount >= MIN_WITHDRAWAL_REQUEST, ErrorCode::WithdrawalTooSmall);
+
+    burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                from: ctx.accounts.provider_share_account.to_account_info(),
+                authority: ctx.accounts.provider.to_account_info(),
+            },
+        ),
+        shares,
+    )?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let config = &mut ctx.accounts.config;
+    let withdrawal_queue = &mut ctx.accounts.withdrawal_queue;
+
+    let request_id = withdrawal_queue.next_request_id;
+    withdrawal_queue.next_request_id += 1;
+    withdrawal_queue.requests.push(WithdrawalRequest {
+        id: request_id,
+        owner: ctx.accounts.provider.key(),
+        amount,
+        requested_at: current_time,
+    });
+
+    let withdrawal_ticket = &mut ctx.accounts.withdrawal_ticket;
+    withdrawal_ticket.id = request_id;
+    withdrawal_ticket.owner = ctx.accounts.provider.key();
+    withdrawal_ticket.amount = amount;
+    withdrawal_ticket.requested_at = current_time;
+    config.withdrawals_pending += amount;
+    msg!("Withdrawal request {} queued for {} wrap sol", request_id, amount);
+
+    emit!(WithdrawalRequestedEvent {
+        request_id,
+        owner: ctx.accounts.provider.key(),
+        shares,
+        amount,
+        timestamp: current_time,
+    });
+
+    fill_withdrawal_queue(config, withdrawal_queue, current_time);
+
+    Ok(())
+}
+
+// Pay out a filled withdrawal request, requests leave the queue once filled
+pub fn claim_withdrawal(ctx: Context<ClaimWithdrawal>, request_id: u64) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_VAULT_WITHDRAWALS)?;
+    require!(!ctx.accounts.withdrawal_queue.is_pending(request_id), ErrorCode::WithdrawalNotFilled);
+    let request = &ctx.accounts.withdrawal_ticket;
+
+    let (_vault_authority, vault_bump) = Pubkey::find_program_address(
+        &[CONFIG_SEED.as_bytes()],
+        ctx.program_id,
+    );
+    let signer_seeds: &[&[u8]] = &[CONFIG_SEED.as_bytes(), &[vault_bump]];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.service_vault.to_account_info(),
+                to: ctx.accounts.provider_token_account.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+                mint: ctx.accounts.wsol_mint.to_account_info(),
+            },
+            &[signer_seeds]
+        ),
+        request.amount,
+        ctx.accounts.wsol_mint.decimals
+    )?;
+
+    let amount = request.amount;
+    let owner = request.owner;
+    ctx.accounts.config.record_claim(amount);
+    msg!("Withdrawal request {} claimed for {} wrap sol", request_id, amount);
+
+    emit!(WithdrawalClaimedEvent {
+        request_id,
+        owner,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Reserve idle liquidity for queued withdrawals strictly oldest first, called whenever sol returns to the vault.
+// Filling stops at the first request the idle liquidity cannot cover, filled requests leave the queue
+// and are claimed with their ticket
+pub fn fill_withdrawal_queue(config: &mut Config, withdrawal_queue: &mut WithdrawalQueue, timestamp: i64) {
+    let mut filled = 0;
+    for request in withdrawal_queue.requests.iter() {
+        let fillable = config.amount.saturating_sub(config.withdrawals_reserved);
+        if request.amount > fillable {
+            break;
+        }
+
+        config.withdrawals_pending -= request.amount;
+        config.withdrawals_reserved += request.amount;
+        filled += 1;
+        msg!("Withdrawal request {} filled for {} wrap sol", request.id, request.amount);
+
+        emit!(WithdrawalFilledEvent {
+            request_id: request.id,
+            owner: request.owner,
+            amount: request.amount,
+            timestamp,
+        });
+    }
+
+    withdrawal_queue.requests.drain(..filled);
+}
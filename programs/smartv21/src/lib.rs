@@ -3,6 +3,7 @@ pub mod error;
 pub mod instructions;
 pub mod state;
 pub mod event;
+pub mod oracle;
 
 use anchor_lang::prelude::*;
 
@@ -33,6 +34,226 @@ pub mod smartv21 {
         instructions::update_service_fee(ctx, new_fixed_fee)
     }
 
+    pub fn update_fee_schedule(
+        ctx: Context<UpdateConfig>,
+        fixed_fee: u64,
+        fee_bps: u64,
+        min_fee: u64,
+        max_fee: u64
+    ) -> Result<()> {
+        instructions::update_fee_schedule(ctx, fixed_fee, fee_bps, min_fee, max_fee)
+    }
+
+    pub fn update_fee_split(
+        ctx: Context<UpdateConfig>,
+        treasury_fee_bps: u64,
+        insurance_fee_bps: u64,
+        referrer_fee_bps: u64
+    ) -> Result<()> {
+        instructions::update_fee_split(ctx, treasury_fee_bps, insurance_fee_bps, referrer_fee_bps)
+    }
+
+    pub fn initialize_fee_vaults(ctx: Context<InitializeFeeVaults>) -> Result<()> {
+        instructions::initialize_fee_vaults(ctx)
+    }
+
+    pub fn withdraw_treasury(
+        ctx: Context<WithdrawTreasury>,
+        amount: u64
+    ) -> Result<()> {
+        instructions::withdraw_treasury(ctx, amount)
+    }
+
+    pub fn cover_bad_debt(ctx: Context<CoverBadDebt>) -> Result<()> {
+        instructions::cover_bad_debt(ctx)
+    }
+
+    pub fn update_interest_rate(
+        ctx: Context<UpdateConfig>,
+        interest_rate_bps: u64
+    ) -> Result<()> {
+        instructions::update_interest_rate(ctx, interest_rate_bps)
+    }
+
+    pub fn propose_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    pub fn set_syncer(ctx: Context<UpdateConfig>, syncer: Pubkey) -> Result<()> {
+        instructions::set_syncer(ctx, syncer)
+    }
+
+    pub fn set_verifier(ctx: Context<UpdateConfig>, verifier: Pubkey) -> Result<()> {
+        instructions::set_verifier(ctx, verifier)
+    }
+
+    pub fn cancel_admin_transfer(ctx: Context<GuardianAction>) -> Result<()> {
+        instructions::cancel_admin_transfer(ctx)
+    }
+
+    pub fn add_guardian(ctx: Context<UpdateConfig>, guardian: Pubkey) -> Result<()> {
+        instructions::add_guardian(ctx, guardian)
+    }
+
+    pub fn remove_guardian(ctx: Context<UpdateConfig>, guardian: Pubkey) -> Result<()> {
+        instructions::remove_guardian(ctx, guardian)
+    }
+
+    pub fn set_timelock(
+        ctx: Context<UpdateConfig>,
+        delay: i64,
+        large_withdrawal_threshold: u64
+    ) -> Result<()> {
+        instructions::set_timelock(ctx, delay, large_withdrawal_threshold)
+    }
+
+    pub fn queue_action(ctx: Context<QueueAction>, action: AdminAction) -> Result<()> {
+        instructions::queue_action(ctx, action)
+    }
+
+    pub fn execute_action(ctx: Context<ExecuteAction>) -> Result<()> {
+        instructions::execute_action(ctx)
+    }
+
+    pub fn cancel_action(ctx: Context<CancelAction>) -> Result<()> {
+        instructions::cancel_action(ctx)
+    }
+
+    pub fn set_admin_signers(
+        ctx: Context<UpdateConfig>,
+        signers: [Pubkey; MAX_ADMIN_SIGNERS],
+        threshold: u8
+    ) -> Result<()> {
+        instructions::set_admin_signers(ctx, signers, threshold)
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: AdminAction) -> Result<()> {
+        instructions::create_proposal(ctx, action)
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        instructions::approve_proposal(ctx)
+    }
+
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        instructions::execute_proposal(ctx)
+    }
+
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        instructions::cancel_proposal(ctx)
+    }
+
+    pub fn pause(ctx: Context<GuardianAction>, operations: u8) -> Result<()> {
+        instructions::pause(ctx, operations)
+    }
+
+    pub fn unpause(ctx: Context<UpdateConfig>, operations: u8) -> Result<()> {
+        instructions::unpause(ctx, operations)
+    }
+
+    pub fn update_lending_caps(
+        ctx: Context<UpdateConfig>,
+        max_utilization_bps: u64,
+        max_active_loans: u64,
+        max_loans_per_creator: u64,
+        max_lent_per_window: u64,
+        lending_window: i64
+    ) -> Result<()> {
+        instructions::update_lending_caps(
+            ctx,
+            max_utilization_bps,
+            max_active_loans,
+            max_loans_per_creator,
+            max_lent_per_window,
+            lending_window
+        )
+    }
+
+    pub fn update_liquidation_bounty(
+        ctx: Context<UpdateConfig>,
+        bounty_bps: u64
+    ) -> Result<()> {
+        instructions::update_liquidation_bounty(ctx, bounty_bps)
+    }
+
+    pub fn update_liquidation_penalty(
+        ctx: Context<UpdateConfig>,
+        penalty_bps: u64
+    ) -> Result<()> {
+        instructions::update_liquidation_penalty(ctx, penalty_bps)
+    }
+
+    pub fn update_liquidation_slippage(
+        ctx: Context<UpdateConfig>,
+        slippage_bps: u64
+    ) -> Result<()> {
+        instructions::update_liquidation_slippage(ctx, slippage_bps)
+    }
+
+    pub fn update_auction_params(
+        ctx: Context<UpdateConfig>,
+        decay: AuctionDecay,
+        decay_period: i64,
+        start_price_bps: u64,
+        floor_price_bps: u64,
+        max_duration: i64
+    ) -> Result<()> {
+        instructions::update_auction_params(ctx, decay, decay_period, start_price_bps, floor_price_bps, max_duration)
+    }
+
+    pub fn update_health_threshold(
+        ctx: Context<UpdateConfig>,
+        health_threshold_bps: u64
+    ) -> Result<()> {
+        instructions::update_health_threshold(ctx, health_threshold_bps)
+    }
+
+    pub fn update_grace_period(
+        ctx: Context<UpdateConfig>,
+        grace_period: i64,
+        late_fee_bps: u64
+    ) -> Result<()> {
+        instructions::update_grace_period(ctx, grace_period, late_fee_bps)
+    }
+
+    pub fn add_loan_tier(
+        ctx: Context<AddLoanTier>,
+        tier_id: u16,
+        params: LoanTierParams
+    ) -> Result<()> {
+        instructions::add_loan_tier(ctx, tier_id, params)
+    }
+
+    pub fn update_loan_tier(
+        ctx: Context<ManageLoanTier>,
+        params: LoanTierParams
+    ) -> Result<()> {
+        instructions::update_loan_tier(ctx, params)
+    }
+
+    pub fn disable_loan_tier(ctx: Context<ManageLoanTier>) -> Result<()> {
+        instructions::disable_loan_tier(ctx)
+    }
+
+    pub fn enable_loan_tier(ctx: Context<ManageLoanTier>) -> Result<()> {
+        instructions::enable_loan_tier(ctx)
+    }
+
+    pub fn set_loan_durations(
+        ctx: Context<SetLoanDurations>,
+        options: Vec<LoanDurationOption>
+    ) -> Result<()> {
+        instructions::set_loan_durations(ctx, options)
+    }
+
+    pub fn list_loan_tiers(ctx: Context<ListLoanTiers>) -> Result<Vec<LoanTier>> {
+        instructions::list_loan_tiers(ctx)
+    }
+
     pub fn create_liquidity_pool(
         ctx: Context<CreateLiquidityPool>,
         init_amount_0: u64,
@@ -61,6 +282,65 @@ pub mod smartv21 {
         instructions::withdraw(ctx, amount)
     }
 
+    pub fn initialize_share_mint(ctx: Context<InitializeShareMint>) -> Result<()> {
+        instructions::initialize_share_mint(ctx)
+    }
+
+    pub fn seed_shares(ctx: Context<SeedShares>) -> Result<()> {
+        instructions::seed_shares(ctx)
+    }
+
+    pub fn deposit_liquidity(
+        ctx: Context<ManageLiquidity>,
+        amount: u64
+    ) -> Result<()> {
+        instructions::deposit_liquidity(ctx, amount)
+    }
+
+    pub fn redeem(
+        ctx: Context<ManageLiquidity>,
+        shares: u64
+    ) -> Result<()> {
+        instructions::redeem(ctx, shares)
+    }
+
+    pub fn initialize_withdrawal_queue(ctx: Context<InitializeWithdrawalQueue>) -> Result<()> {
+        instructions::initialize_withdrawal_queue(ctx)
+    }
+
+    pub fn request_withdrawal(
+        ctx: Context<RequestWithdrawal>,
+        shares: u64
+    ) -> Result<()> {
+        instructions::request_withdrawal(ctx, shares)
+    }
+
+    pub fn claim_withdrawal(
+        ctx: Context<ClaimWithdrawal>,
+        request_id: u64
+    ) -> Result<()> {
+        instructions::claim_withdrawal(ctx, request_id)
+    }
+
+    pub fn migrate_config(
+        ctx: Context<MigrateConfig>,
+        principal_outstanding: u64
+    ) -> Result<()> {
+        instructions::migrate_config(ctx, principal_outstanding)
+    }
+
+    pub fn migrate_pool_loan(ctx: Context<MigratePoolLoan>) -> Result<()> {
+        instructions::migrate_pool_loan(ctx)
+    }
+
+    pub fn get_vault_accounting(ctx: Context<GetVaultAccounting>) -> Result<VaultAccounting> {
+        instructions::get_vault_accounting(ctx)
+    }
+
+    pub fn get_loan_health(ctx: Context<GetLoanHealth>) -> Result<LoanHealth> {
+        instructions::get_loan_health(ctx)
+    }
+
     pub fn remove_liquidity(
         ctx: Context<RemoveLiquidity>,
         lp_token_amount: u64,
@@ -70,12 +350,36 @@ pub mod smartv21 {
         instructions::remove_liquidity(ctx, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount)
     }
 
+    pub fn repay_with_sol(ctx: Context<RepayWithSol>) -> Result<()> {
+        instructions::repay_with_sol(ctx)
+    }
+
+    pub fn extend_loan(ctx: Context<ExtendLoan>) -> Result<()> {
+        instructions::extend_loan(ctx)
+    }
+
     pub fn liquidate_loan(
         ctx: Context<LiquidateLoan>,
         lp_token_amount: u64,
         minimum_token_0_amount: u64,
-        minimum_token_1_amount: u64
+        minimum_token_1_amount: u64,
+        minimum_sol_out: Option<u64>
+    ) -> Result<()> {
+        instructions::liquidate_loan(ctx, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount, minimum_sol_out)
+    }
+
+    pub fn start_liquidation_auction(ctx: Context<StartLiquidationAuction>) -> Result<()> {
+        instructions::start_liquidation_auction(ctx)
+    }
+
+    pub fn bid_liquidation_auction(
+        ctx: Context<BidLiquidationAuction>,
+        max_price: u64
     ) -> Result<()> {
-        instructions::liquidate_loan(ctx, lp_token_amount, minimum_token_0_amount, minimum_token_1_amount)
+        instructions::bid_liquidation_auction(ctx, max_price)
+    }
+
+    pub fn close_expired_auction(ctx: Context<CloseExpiredAuction>) -> Result<()> {
+        instructions::close_expired_auction(ctx)
     }
 }
@@ -0,0 +1,358 @@
+use anchor_lang::prelude::*;
+use raydium_cpmm_cpi::states::{ObservationState, PoolState, OBSERVATION_NUM};
+
+use crate::{ constants::{FEE_RATE_DENOMINATOR, TWAP_WINDOW}, error::ErrorCode };
+
+// Time weighted price of token_0 in token_1 over roughly the last `window` seconds up to current_time, scaled by 2^32.
+// The pool only writes an observation when it is swapped, the time since the latest one is counted at the spot price
+pub fn twap_price_0_x32(
+    observation_state: &ObservationState,
+    spot_price_0_x32: u128,
+    current_time: u64,
+    window: u64,
+) -> Result<u128> {
+    require!(observation_state.initialized, ErrorCode::OracleUnavailable);
+
+    let latest_index = observation_state.observation_index as usize % OBSERVATION_NUM;
+    let latest = observation_state.observations[latest_index];
+    let latest_time = latest.block_timestamp;
+    let now = current_time.max(latest_time);
+    let cumulative_now = latest.cumulative_token_0_price_x32
+        .wrapping_add(spot_price_0_x32.wrapping_mul((now - latest_time) as u128));
+
+    // Walk back through the ring buffer to the oldest observation inside the window
+    let mut oldest = latest;
+    for offset in 1..OBSERVATION_NUM {
+        if now - oldest.block_timestamp >= window {
+            break;
+        }
+
+        let index = (latest_index + OBSERVATION_NUM - offset) % OBSERVATION_NUM;
+        let observation = observation_state.observations[index];
+        let observation_time = observation.block_timestamp;
+        if observation_time == 0 || observation_time >= oldest.block_timestamp {
+            break;
+        }
+        oldest = observation;
+    }
+
+    let elapsed = now - oldest.block_timestamp;
+    require!(elapsed > 0, ErrorCode::OracleUnavailable);
+
+    let cumulative_delta = cumulative_now.wrapping_sub(oldest.cumulative_token_0_price_x32);
+
+    Ok(cumulative_delta / elapsed as u128)
+}
+
+// Spot price of token_0 in token_1 at the given reserves, scaled by 2^32 like the pool observations
+pub fn spot_price_0_x32(reserve_0: u64, reserve_1: u64) -> Result<u128> {
+    require!(reserve_0 > 0, ErrorCode::OracleUnavailable);
+
+    Ok(((reserve_1 as u128) << 32) / reserve_0 as u128)
+}
+
+// Sol reserve of a constant product pool trading at the given token_0 price, sqrt(k / p) or sqrt(k * p)
+pub fn implied_sol_reserve(
+    reserve_0: u64,
+    reserve_1: u64,
+    price_0_x32: u128,
+    is_token0_sol: bool,
+) -> Result<u64> {
+    require!(price_0_x32 > 0, ErrorCode::OracleUnavailable);
+    let k = reserve_0 as u128 * reserve_1 as u128;
+
+    let squared_reserve = if is_token0_sol {
+        match k.checked_shl(32).filter(|shifted| shifted >> 32 == k) {
+            Some(shifted) => shifted / price_0_x32,
+            None => (k / price_0_x32).checked_shl(32).ok_or(ErrorCode::MathOverflow)?,
+        }
+    } else {
+        match k.checked_mul(price_0_x32) {
+            Some(product) => product >> 32,
+            None => (k >> 32).checked_mul(price_0_x32).ok_or(ErrorCode::MathOverflow)?,
+        }
+    };
+
+    u64::try_from(integer_sqrt(squared_reserve)).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// Sol backing an lp position in the pool, valued at the TWAP instead of the spot reserves
+pub fn lp_sol_value(
+    pool_state: &PoolState,
+    observation_state: &ObservationState,
+    vault_0_amount: u64,
+    vault_1_amount: u64,
+    lp_amount: u64,
+    is_token0_sol: bool,
+    current_time: u64,
+) -> Result<u64> {
+    let lp_supply = pool_state.lp_supply;
+    require!(lp_supply > 0, ErrorCode::OracleUnavailable);
+
+    let (reserve_0, reserve_1) = pool_state.vault_amount_without_fee(vault_0_amount, vault_1_amount);
+    let price_0_x32 = twap_price_0_x32(
+        observation_state,
+        spot_price_0_x32(reserve_0, reserve_1)?,
+        current_time,
+        TWAP_WINDOW,
+    )?;
+    let sol_reserve = implied_sol_reserve(reserve_0, reserve_1, price_0_x32, is_token0_sol)?;
+
+    let value = sol_reserve as u128 * lp_amount as u128 / lp_supply as u128;
+
+    u64::try_from(value).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// Sol a swap of token_amount_in should return from a pool trading at the given token_0 price, after the trade fee
+pub fn expected_swap_sol_out(
+    reserve_0: u64,
+    reserve_1: u64,
+    price_0_x32: u128,
+    is_token0_sol: bool,
+    token_amount_in: u64,
+    trade_fee_rate: u64,
+) -> Result<u64> {
+    let sol_reserve = implied_sol_reserve(reserve_0, reserve_1, price_0_x32, is_token0_sol)?;
+    require!(sol_reserve > 0, ErrorCode::OracleUnavailable);
+    let token_reserve = reserve_0 as u128 * reserve_1 as u128 / sol_reserve as u128;
+
+    swap_amount_out(token_reserve, sol_reserve as u128, token_amount_in, trade_fee_rate)
+}
+
+// Output of a constant product swap, the fee is taken from the input and rounded up like the pool does
+fn swap_amount_out(input_reserve: u128, output_reserve: u128, amount_in: u64, trade_fee_rate: u64) -> Result<u64> {
+    let amount_in = amount_in as u128;
+    let fee = (amount_in * trade_fee_rate as u128).div_ceil(FEE_RATE_DENOMINATOR as u128);
+    let amount_in_after_fee = amount_in.saturating_sub(fee);
+
+    let new_input_reserve = input_reserve
+        .checked_add(amount_in_after_fee)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if new_input_reserve == 0 {
+        return Ok(0);
+    }
+    let amount_out = output_reserve * amount_in_after_fee / new_input_reserve;
+
+    u64::try_from(amount_out).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// Token amounts an lp position redeems for at the current reserves, (token_0, token_1)
+pub fn lp_redeemable(
+    pool_state: &PoolState,
+    vault_0_amount: u64,
+    vault_1_amount: u64,
+    lp_amount: u64,
+) -> Result<(u64, u64)> {
+    let lp_supply = pool_state.lp_supply;
+    if lp_supply == 0 {
+        return Ok((0, 0));
+    }
+
+    let (reserve_0, reserve_1) = pool_state.vault_amount_without_fee(vault_0_amount, vault_1_amount);
+    let share = |reserve: u64| reserve as u128 * lp_amount as u128 / lp_supply as u128;
+
+    Ok((
+        u64::try_from(share(reserve_0)).map_err(|_| error!(ErrorCode::MathOverflow))?,
+        u64::try_from(share(reserve_1)).map_err(|_| error!(ErrorCode::MathOverflow))?,
+    ))
+}
+
+fn integer_sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    // Newton's method starting above the root
+    let mut current = value;
+    let mut next = (value >> 1) + (value & 1);
+    while next < current {
+        current = next;
+        next = (current + value / current) >> 1;
+    }
+
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::__private::bytemuck::Zeroable;
+    use raydium_cpmm_cpi::states::Observation;
+
+    const SOL_RESERVE: u64 = 1_000_000_000;
+    const TOKEN_RESERVE: u64 = 4_000_000_000;
+
+    // Observations written at the given (index, timestamp, token_0 price since the previous one) in order
+    fn observation_state(latest_index: u16, observations: &[(usize, u64, u128)]) -> ObservationState {
+        let mut state = ObservationState::zeroed();
+        state.initialized = true;
+        state.observation_index = latest_index;
+
+        let mut cumulative: u128 = 0;
+        let mut previous_time = observations[0].1;
+        for &(index, block_timestamp, price_0_x32) in observations {
+            cumulative = cumulative.wrapping_add(price_0_x32 * (block_timestamp - previous_time) as u128);
+            previous_time = block_timestamp;
+            state.observations[index] = Observation {
+                block_timestamp,
+                cumulative_token_0_price_x32: cumulative,
+                cumulative_token_1_price_x32: 0,
+            };
+        }
+
+        state
+    }
+
+    #[test]
+    fn twap_walks_back_across_the_ring_buffer_wrap() {
+        // Written at the end of the buffer and wrapped around to the start, the price doubled at 1_600
+        let state = observation_state(1, &[
+            (97, 1_000, 0),
+            (98, 1_600, 2 << 32),
+            (99, 2_200, 4 << 32),
+            (0, 2_800, 4 << 32),
+            (1, 3_400, 4 << 32),
+        ]);
+
+        assert_eq!(twap_price_0_x32(&state, 0, 3_400, 1_800).unwrap(), 4 << 32);
+        assert_eq!(twap_price_0_x32(&state, 0, 3_400, 2_400).unwrap(), 7 << 31);
+    }
+
+    #[test]
+    fn twap_survives_the_cumulative_price_wrapping() {
+        let mut state = observation_state(1, &[(0, 1_000, 0), (1, 1_600, 3 << 32)]);
+        let offset = u128::MAX - 100;
+        for observation in state.observations[..2].iter_mut() {
+            let cumulative = observation.cumulative_token_0_price_x32;
+            observation.cumulative_token_0_price_x32 = cumulative.wrapping_add(offset);
+        }
+
+        assert_eq!(twap_price_0_x32(&state, 0, 1_600, 1_800).unwrap(), 3 << 32);
+    }
+
+    #[test]
+    fn twap_uses_the_observations_available_in_a_short_window() {
+        // A young pool only has 10 seconds of history for a 30 minute window
+        let state = observation_state(1, &[(0, 1_000, 0), (1, 1_010, 5 << 32)]);
+        assert_eq!(twap_price_0_x32(&state, 0, 1_010, 1_800).unwrap(), 5 << 32);
+
+        // Slots never written stop the walk back
+        let state = observation_state(2, &[(1, 1_000, 0), (2, 1_300, 5 << 32)]);
+        assert_eq!(twap_price_0_x32(&state, 0, 1_300, 1_800).unwrap(), 5 << 32);
+    }
+
+    #[test]
+    fn twap_needs_two_observations() {
+        let state = observation_state(0, &[(0, 1_000, 0)]);
+        assert!(twap_price_0_x32(&state, 3 << 32, 1_000, 1_800).is_err());
+
+        let mut state = observation_state(1, &[(0, 1_000, 0), (1, 1_600, 3 << 32)]);
+        state.initialized = false;
+        assert!(twap_price_0_x32(&state, 3 << 32, 1_600, 1_800).is_err());
+    }
+
+    #[test]
+    fn twap_counts_the_time_since_the_latest_observation_at_the_spot_price() {
+        // Nobody swapped since 1_600, the price moved to 4 since then
+        let state = observation_state(1, &[(0, 1_000, 0), (1, 1_600, 2 << 32)]);
+
+        assert_eq!(twap_price_0_x32(&state, 4 << 32, 2_500, 1_800).unwrap(), (4_800 << 32) / 1_500);
+        assert_eq!(twap_price_0_x32(&state, 4 << 32, 5_200, 1_800).unwrap(), 4 << 32);
+
+        // A single observation is enough once time has passed since it
+        let state = observation_state(0, &[(0, 1_000, 0)]);
+        assert_eq!(twap_price_0_x32(&state, 4 << 32, 1_060, 1_800).unwrap(), 4 << 32);
+
+        // A clock behind the latest observation does not extend it
+        let state = observation_state(1, &[(0, 1_000, 0), (1, 1_600, 2 << 32)]);
+        assert_eq!(twap_price_0_x32(&state, 4 << 32, 1_500, 1_800).unwrap(), 2 << 32);
+    }
+
+    #[test]
+    fn spot_price_matches_the_reserves() {
+        assert_eq!(spot_price_0_x32(SOL_RESERVE, TOKEN_RESERVE).unwrap(), 4 << 32);
+        assert_eq!(spot_price_0_x32(TOKEN_RESERVE, SOL_RESERVE).unwrap(), 1 << 30);
+        assert!(spot_price_0_x32(0, SOL_RESERVE).is_err());
+    }
+
+    #[test]
+    fn implied_sol_reserve_at_the_spot_price_is_the_sol_reserve() {
+        assert_eq!(implied_sol_reserve(SOL_RESERVE, TOKEN_RESERVE, 4 << 32, true).unwrap(), SOL_RESERVE);
+        assert_eq!(implied_sol_reserve(TOKEN_RESERVE, SOL_RESERVE, 1 << 30, false).unwrap(), SOL_RESERVE);
+    }
+
+    #[test]
+    fn implied_sol_reserve_follows_the_price() {
+        // Sol worth 16 tokens instead of 4 leaves half the sol in the pool
+        assert_eq!(implied_sol_reserve(SOL_RESERVE, TOKEN_RESERVE, 16 << 32, true).unwrap(), SOL_RESERVE / 2);
+        assert_eq!(implied_sol_reserve(TOKEN_RESERVE, SOL_RESERVE, 1 << 28, false).unwrap(), SOL_RESERVE / 2);
+        assert!(implied_sol_reserve(SOL_RESERVE, TOKEN_RESERVE, 0, true).is_err());
+    }
+
+    #[test]
+    fn implied_sol_reserve_handles_reserves_too_large_to_shift() {
+        let reserve = implied_sol_reserve(u64::MAX, u64::MAX, 1 << 32, true).unwrap();
+        assert!(u64::MAX - reserve <= 1);
+
+        let reserve = implied_sol_reserve(u64::MAX, u64::MAX, 1 << 32, false).unwrap();
+        assert!(u64::MAX - reserve <= 1);
+    }
+
+    #[test]
+    fn integer_sqrt_rounds_down() {
+        assert_eq!(integer_sqrt(0), 0);
+        assert_eq!(integer_sqrt(1), 1);
+        assert_eq!(integer_sqrt(3), 1);
+        assert_eq!(integer_sqrt(4), 2);
+        assert_eq!(integer_sqrt(15), 3);
+        assert_eq!(integer_sqrt(16), 4);
+        assert_eq!(integer_sqrt(1_000_000_000_000_000_000), 1_000_000_000);
+        assert_eq!(integer_sqrt(u128::MAX), u64::MAX as u128);
+
+        for value in [2u128, 99, 12_345_678_901, u64::MAX as u128 * 3] {
+            let root = integer_sqrt(value);
+            assert!(root * root <= value && (root + 1) * (root + 1) > value);
+        }
+    }
+
+    #[test]
+    fn swap_quote_at_spot_price_matches_constant_product() {
+        // token_0 is sol and trades for 4 tokens
+        let quote = expected_swap_sol_out(SOL_RESERVE, TOKEN_RESERVE, 4 << 32, true, 400_000_000, 0).unwrap();
+        assert_eq!(quote, 90_909_090);
+
+        // Same pool with the tokens as token_0, each worth a quarter sol
+        let quote = expected_swap_sol_out(TOKEN_RESERVE, SOL_RESERVE, 1 << 30, false, 400_000_000, 0).unwrap();
+        assert_eq!(quote, 90_909_090);
+    }
+
+    #[test]
+    fn swap_quote_takes_the_trade_fee_from_the_input() {
+        // 0.25% of 400_000_000 is taken before the swap
+        let quote = expected_swap_sol_out(SOL_RESERVE, TOKEN_RESERVE, 4 << 32, true, 400_000_000, 2_500).unwrap();
+        assert_eq!(quote, 90_702_432);
+
+        // The fee is rounded up, a single token pays it all
+        let quote = expected_swap_sol_out(SOL_RESERVE, TOKEN_RESERVE, 4 << 32, true, 1, 2_500).unwrap();
+        assert_eq!(quote, 0);
+    }
+
+    #[test]
+    fn swap_quote_ignores_spot_reserves_pushed_off_the_twap() {
+        // Reserves after someone sold tokens into the pool, the TWAP still prices sol at 4 tokens
+        let k = SOL_RESERVE as u128 * TOKEN_RESERVE as u128;
+        let pushed_sol_reserve = 500_000_000;
+        let pushed_token_reserve = (k / pushed_sol_reserve as u128) as u64;
+
+        let at_twap = expected_swap_sol_out(pushed_sol_reserve, pushed_token_reserve, 4 << 32, true, 400_000_000, 0).unwrap();
+        assert_eq!(at_twap, 90_909_090);
+
+        let at_spot = swap_amount_out(pushed_token_reserve as u128, pushed_sol_reserve as u128, 400_000_000, 0).unwrap();
+        assert!(at_spot < at_twap / 2);
+    }
+
+    #[test]
+    fn swap_quote_requires_a_price() {
+        assert!(expected_swap_sol_out(SOL_RESERVE, TOKEN_RESERVE, 0, true, 400_000_000, 0).is_err());
+    }
+}
@@ -1,14 +1,66 @@
 use anchor_lang::prelude::*;
 
+use crate::{ constants::*, error::ErrorCode };
+
 // Account Structures
 #[account]
 pub struct Config {
-    pub amount: u64, // wrap sol amount
+    pub amount: u64, // idle wrap sol in the service vault, available to lend
     pub admin: Pubkey,
     pub syncer: Pubkey,
     pub verifier: Pubkey,
     pub service_fee: u64, // Fixed service fee in lamports
     pub is_paused: bool,
+    pub interest_rate_bps: u64, // Annualised interest rate on borrowed sol in basis points
+    pub service_fee_bps: u64, // Service fee share of the borrowed sol in basis points
+    pub min_service_fee: u64, // Floor of the service fee in lamports
+    pub max_service_fee: u64, // Ceiling of the service fee in lamports, 0 for no ceiling
+    pub treasury_fee_bps: u64, // Share of fees sent to the treasury
+    pub insurance_fee_bps: u64, // Share of fees sent to the insurance fund
+    pub referrer_fee_bps: u64, // Share of fees sent to the referrer, kept by the vault without one
+    pub principal_outstanding: u64, // wrap sol lent out in active loans
+    pub fees_accumulated: u64, // Lifetime fee revenue kept by the service vault
+    pub interest_accumulated: u64, // Lifetime interest received by the service vault
+    pub realized_losses: u64, // Lifetime principal not recovered from liquidations
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub lifetime_lent: u64,
+    pub lifetime_repaid: u64, // Lifetime principal returned to the service vault
+    pub loans_originated: u64,
+    pub withdrawals_pending: u64, // wrap sol owed to queued withdrawals not yet filled
+    pub withdrawals_reserved: u64, // Idle wrap sol set aside for filled withdrawals awaiting claim
+    pub max_utilization_bps: u64, // Highest share of vault assets lent out, 0 for no cap
+    pub max_active_loans: u64, // 0 for no cap
+    pub max_loans_per_creator: u64, // 0 for no cap
+    pub max_lent_per_window: u64, // wrap sol lent per lending window, 0 for no cap
+    pub lending_window: i64, // Length of the lending window in seconds
+    pub active_loans: u64,
+    pub window_start: i64, // Start of the current lending window
+    pub window_lent: u64, // wrap sol lent in the current lending window
+    pub pending_admin: Pubkey, // Proposed admin waiting to accept, default when none
+    pub paused_operations: u8, // Bitmask of PAUSE_* operations currently halted
+    pub guardians: [Pubkey; MAX_GUARDIANS], // Can pause and cancel pending admin actions, default for an empty slot
+    pub timelock_delay: i64, // Seconds between queueing and executing an admin action, 0 to allow direct changes
+    pub large_withdrawal_threshold: u64, // Vault withdrawals from this amount must go through the timelock
+    pub next_action_id: u64,
+    pub admin_signers: [Pubkey; MAX_ADMIN_SIGNERS], // M-of-N admin keys, default for an empty slot
+    pub admin_threshold: u8, // Approvals needed for a proposal, 0 while the single admin key is in charge
+    pub next_proposal_id: u64,
+    pub liquidation_bounty_bps: u64, // Share of the recovered sol paid to the liquidator
+    pub grace_period: i64, // Seconds after expiry where only the creator can repay, before liquidation opens
+    pub late_fee_bps: u64, // Fee on the amount owed charged once when repaying during the grace period
+    pub liquidation_penalty_bps: u64, // Share of the recovered sol sent to the treasury, taken from the creator's surplus
+    pub auction_decay: AuctionDecay,
+    pub auction_decay_period: i64, // 0 disables liquidation auctions
+    pub auction_start_price_bps: u64, // Start price as a share of the amount owed
+    pub auction_floor_price_bps: u64, // Floor price as a share of the amount owed
+    pub bad_debt_outstanding: u64, // Principal lost in liquidations not yet covered by the insurance fund
+    pub bad_debt_total: u64, // Lifetime principal lost in liquidations
+    pub health_threshold_bps: u64, // Loans can be liquidated early once the escrowed sol falls below this share of init_sol_amount, 0 disables
+    pub withdrawal_window_start: i64, // Start of the current direct withdrawal window, one timelock delay long
+    pub withdrawn_in_window: u64, // wrap sol withdrawn directly in the current withdrawal window
+    pub liquidation_slippage_bps: u64, // Largest shortfall of the sol a liquidation recovers under its value at the pool TWAP
+    pub auction_max_duration: i64, // Seconds an auction stays open, direct liquidation reopens once it expires
 }
 #[account]
 pub struct PoolLoan {
@@ -21,15 +73,529 @@ pub struct PoolLoan {
     pub loan_start_time: i64,
     pub loan_duration: i64,
     pub is_repaid: bool,
+    pub tier_id: u16, // Loan tier the pool was created with
+    pub extension_count: u8, // Number of times the loan has been extended
+    pub principal_outstanding: u64, // wrap sol still owed to the service vault
+    pub interest_rate_bps: u64, // Annualised interest rate locked in at creation
+    pub interest_accrued: u64, // Interest owed but not yet paid
+    pub last_accrual_time: i64,
+    pub late_fee_charged: bool,
+    pub in_auction: bool, // The escrowed lp is on sale in a liquidation auction
+    pub shortfall: u64, // Principal and interest a liquidation failed to recover
+    pub bad_debt: u64, // Principal a liquidation failed to recover
+}
+#[account]
+pub struct LoanTier {
+    pub tier_id: u16,
+    pub sol_amount: u64, // wrap sol lent for this tier
+    pub min_duration: i64, // shortest loan duration allowed in seconds
+    pub max_duration: i64, // longest loan duration allowed in seconds
+    pub service_fee: u64, // Tier fee in lamports for the base duration, charged on top of the config service fee
+    pub is_enabled: bool,
+    pub extension_duration: i64, // seconds added to the loan per extension
+    pub extension_fee: u64, // Fee in lamports per extension
+    pub max_extensions: u8,
+}
+#[account]
+pub struct CreatorStats {
+    pub creator: Pubkey,
+    pub active_loans: u64, // Loans of the creator not yet repaid or liquidated
+}
+#[account]
+pub struct PendingAction {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    pub queued_at: i64,
+    pub eta: i64, // Earliest time the action can be executed
+}
+#[account]
+pub struct AdminProposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    pub approvals: [Pubkey; MAX_ADMIN_SIGNERS], // Admin signers that approved, default for an empty slot
+    pub created_at: i64,
+    pub threshold_reached_at: i64, // 0 until enough approvals, starts the timelock delay
+}
+#[account]
+pub struct LiquidationAuction {
+    pub pool_loan: Pubkey,
+    pub starter: Pubkey, // Paid the rent, refunded when the auction settles
+    pub lp_amount: u64, // Escrowed lp tokens on sale
+    pub start_price: u64, // wrap sol
+    pub floor_price: u64, // wrap sol
+    pub decay: AuctionDecay,
+    pub decay_period: i64,
+    pub start_time: i64,
+    pub expires_at: i64, // No bids after this, the loan can be liquidated directly
+}
+#[account]
+pub struct LoanDurations {
+    pub options: Vec<LoanDurationOption>, // Loan durations creators can choose from
+}
+
+// Admin supplied values for a loan tier
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LoanTierParams {
+    pub sol_amount: u64,
+    pub min_duration: i64,
+    pub max_duration: i64,
+    pub service_fee: u64,
+    pub extension_duration: i64,
+    pub extension_fee: u64,
+    pub max_extensions: u8,
+}
+#[account]
+pub struct WithdrawalQueue {
+    pub next_request_id: u64,
+    pub requests: Vec<WithdrawalRequest>, // Requests not filled yet, oldest first
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct WithdrawalRequest {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub amount: u64, // wrap sol owed, priced when the shares were burned
+    pub requested_at: i64,
+}
+#[account]
+pub struct WithdrawalTicket {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub amount: u64, // wrap sol owed, priced when the shares were burned
+    pub requested_at: i64,
+}
+
+// Snapshot of the vault accounting returned by get_vault_accounting
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct VaultAccounting {
+    pub vault_balance: u64,
+    pub idle_liquidity: u64,
+    pub principal_outstanding: u64,
+    pub fees_accumulated: u64,
+    pub interest_accumulated: u64,
+    pub realized_losses: u64,
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub lifetime_lent: u64,
+    pub lifetime_repaid: u64,
+    pub loans_originated: u64,
+    pub withdrawals_pending: u64,
+    pub withdrawals_reserved: u64,
+    pub active_loans: u64,
+    pub window_lent: u64,
+    pub bad_debt_outstanding: u64,
+    pub bad_debt_total: u64,
+}
+
+// Snapshot of a loan's health returned by get_loan_health
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LoanHealth {
+    pub sol_redeemable: u64, // wrap sol the escrowed lp redeems for at the current reserves
+    pub token_redeemable: u64,
+    pub twap_sol_value: u64, // wrap sol backing the escrowed lp at the pool TWAP
+    pub amount_owed: u64, // principal, interest and late fee owed now
+    pub time_to_expiry: i64, // seconds until the deadline, negative once expired
+    pub health_ratio_bps: u64, // sol_redeemable over amount_owed
+    pub is_liquidatable: bool,
+}
+// Admin changes that can be queued behind the timelock
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum AdminAction {
+    UpdateFeeSchedule { fixed_fee: u64, fee_bps: u64, min_fee: u64, max_fee: u64 },
+    UpdateFeeSplit { treasury_fee_bps: u64, insurance_fee_bps: u64, referrer_fee_bps: u64 },
+    UpdateInterestRate { interest_rate_bps: u64 },
+    UpdateLendingCaps {
+        max_utilization_bps: u64,
+        max_active_loans: u64,
+        max_loans_per_creator: u64,
+        max_lent_per_window: u64,
+        lending_window: i64,
+    },
+    ProposeAdmin { new_admin: Pubkey },
+    SetSyncer { syncer: Pubkey },
+    SetVerifier { verifier: Pubkey },
+    SetTimelock { delay: i64, large_withdrawal_threshold: u64 },
+    Withdraw { amount: u64, recipient: Pubkey }, // recipient is the receiving token account
+    AddGuardian { guardian: Pubkey },
+    RemoveGuardian { guardian: Pubkey },
+    Unpause { operations: u8 },
+    SetAdminSigners { signers: [Pubkey; MAX_ADMIN_SIGNERS], threshold: u8 },
+    WithdrawTreasury { amount: u64, recipient: Pubkey },
+    UpdateLiquidationBounty { bounty_bps: u64 },
+    UpdateGracePeriod { grace_period: i64, late_fee_bps: u64 },
+    UpdateLiquidationPenalty { penalty_bps: u64 },
+    UpdateAuctionParams { decay: AuctionDecay, decay_period: i64, start_price_bps: u64, floor_price_bps: u64, max_duration: i64 },
+    UpdateHealthThreshold { health_threshold_bps: u64 },
+    UpdateLiquidationSlippage { slippage_bps: u64 },
+    UpdateLoanTier { tier_id: u16, params: LoanTierParams },
+    SetLoanDurations { options: [LoanDurationOption; MAX_LOAN_DURATIONS], count: u8 }, // first count options are used
+    SetLoanTierEnabled { tier_id: u16, is_enabled: bool },
+}
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionDecay {
+    Linear, // Reaches the floor price after one decay period
+    Exponential, // Halves every decay period until the floor price
+}
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Syncer,
+    Verifier,
+}
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct FeeSplit {
+    pub treasury: u64,
+    pub insurance: u64,
+    pub referrer: u64,
+    pub vault: u64,
+}
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LoanDurationOption {
+    pub duration: i64, // loan duration in seconds
+    pub fee_multiplier_bps: u32, // Tier fee scale for this duration, 10000 = 1x
 }
 
 impl Config {
-    pub const LEN: usize = 8 + // wrap sol amount (u64)
+    pub const LEN: usize = 8 + // idle wrap sol amount (u64)
                            32 +  // admin
                            32 +  // syncer
                            32 +  // verifier
                            8 +   // fixed_service_fee (u64)
-                           1;    // is_paused (bool)
+                           1 +   // is_paused (bool)
+                           8 +   // interest_rate_bps (u64)
+                           8 +   // service_fee_bps (u64)
+                           8 +   // min_service_fee (u64)
+                           8 +   // max_service_fee (u64)
+                           8 +   // treasury_fee_bps (u64)
+                           8 +   // insurance_fee_bps (u64)
+                           8 +   // referrer_fee_bps (u64)
+                           8 +   // principal_outstanding (u64)
+                           8 +   // fees_accumulated (u64)
+                           8 +   // interest_accumulated (u64)
+                           8 +   // realized_losses (u64)
+                           8 +   // total_deposited (u64)
+                           8 +   // total_withdrawn (u64)
+                           8 +   // lifetime_lent (u64)
+                           8 +   // lifetime_repaid (u64)
+                           8 +   // loans_originated (u64)
+                           8 +   // withdrawals_pending (u64)
+                           8 +   // withdrawals_reserved (u64)
+                           8 +   // max_utilization_bps (u64)
+                           8 +   // max_active_loans (u64)
+                           8 +   // max_loans_per_creator (u64)
+                           8 +   // max_lent_per_window (u64)
+                           8 +   // lending_window (i64)
+                           8 +   // active_loans (u64)
+                           8 +   // window_start (i64)
+                           8 +   // window_lent (u64)
+                           32 +  // pending_admin
+                           1 +   // paused_operations (u8)
+                           32 * MAX_GUARDIANS + // guardians
+                           8 +   // timelock_delay (i64)
+                           8 +   // large_withdrawal_threshold (u64)
+                           8 +   // next_action_id (u64)
+                           32 * MAX_ADMIN_SIGNERS + // admin_signers
+                           1 +   // admin_threshold (u8)
+                           8 +   // next_proposal_id (u64)
+                           8 +   // liquidation_bounty_bps (u64)
+                           8 +   // grace_period (i64)
+                           8 +   // late_fee_bps (u64)
+                           8 +   // liquidation_penalty_bps (u64)
+                           1 +   // auction_decay (AuctionDecay)
+                           8 +   // auction_decay_period (i64)
+                           8 +   // auction_start_price_bps (u64)
+                           8 +   // auction_floor_price_bps (u64)
+                           8 +   // bad_debt_outstanding (u64)
+                           8 +   // bad_debt_total (u64)
+                           8 +   // health_threshold_bps (u64)
+                           8 +   // withdrawal_window_start (i64)
+                           8 +   // withdrawn_in_window (u64)
+                           8 +   // liquidation_slippage_bps (u64)
+                           8;    // auction_max_duration (i64)
+
+    // is_paused halts every operation, paused_operations halts them individually
+    pub fn require_not_paused(&self, operation: u8) -> Result<()> {
+        require!(!self.is_paused && self.paused_operations & operation == 0, ErrorCode::ProgramPaused);
+
+        Ok(())
+    }
+
+    pub fn set_paused_operations(&mut self, paused_operations: u8) -> Result<()> {
+        require!(paused_operations & !PAUSE_ALL == 0, ErrorCode::InvalidPauseMask);
+        self.paused_operations = paused_operations;
+        self.is_paused = paused_operations == PAUSE_ALL;
+
+        Ok(())
+    }
+
+    // Admin changes covered by the timelock can only be made directly while it is off
+    pub fn require_no_timelock(&self) -> Result<()> {
+        require!(self.timelock_delay == 0, ErrorCode::TimelockRequired);
+
+        Ok(())
+    }
+
+    // Direct withdrawals while the timelock is on are capped per timelock delay, so a large
+    // withdrawal split across several calls still has to be queued
+    pub fn record_direct_withdrawal(&mut self, amount: u64, current_time: i64) -> Result<()> {
+        if self.timelock_delay == 0 {
+            return Ok(());
+        }
+
+        if current_time >= self.withdrawal_window_start.saturating_add(self.timelock_delay) {
+            self.withdrawal_window_start = current_time;
+            self.withdrawn_in_window = 0;
+        }
+        let withdrawn = self.withdrawn_in_window
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(withdrawn < self.large_withdrawal_threshold, ErrorCode::TimelockRequired);
+        self.withdrawn_in_window = withdrawn;
+
+        Ok(())
+    }
+
+    pub fn is_admin_signer(&self, key: &Pubkey) -> bool {
+        *key != Pubkey::default() && self.admin_signers.contains(key)
+    }
+
+    pub fn admin_signer_count(&self) -> usize {
+        self.admin_signers.iter().filter(|key| **key != Pubkey::default()).count()
+    }
+
+    pub fn is_guardian(&self, key: &Pubkey) -> bool {
+        *key != Pubkey::default() && self.guardians.contains(key)
+    }
+
+    // Vault accounting, every movement of wrap sol in or out of the service vault goes through these
+    pub fn record_deposit(&mut self, amount: u64) {
+        self.amount += amount;
+        self.total_deposited += amount;
+    }
+
+    pub fn record_withdrawal(&mut self, amount: u64) -> Result<()> {
+        require!(amount <= self.available_liquidity(), ErrorCode::InsufficientLiquidity);
+        self.amount -= amount;
+        self.total_withdrawn += amount;
+
+        Ok(())
+    }
+
+    // Payout of a filled withdrawal request from the reserved liquidity
+    pub fn record_claim(&mut self, amount: u64) {
+        self.withdrawals_reserved -= amount;
+        self.amount -= amount;
+        self.total_withdrawn += amount;
+    }
+
+    pub fn record_fee(&mut self, amount: u64) {
+        self.amount += amount;
+        self.fees_accumulated += amount;
+    }
+
+    pub fn record_loan(&mut self, principal: u64, current_time: i64) -> Result<()> {
+        require!(principal <= self.available_liquidity(), ErrorCode::InsufficientBalance);
+        self.check_lending_caps(principal, current_time)?;
+
+        if self.lending_window > 0 && current_time >= self.window_start.saturating_add(self.lending_window) {
+            self.window_start = current_time;
+            self.window_lent = 0;
+        }
+        self.amount -= principal;
+        self.principal_outstanding += principal;
+        self.lifetime_lent += principal;
+        self.loans_originated += 1;
+        self.active_loans += 1;
+        self.window_lent += principal;
+
+        Ok(())
+    }
+
+    pub fn record_loan_closed(&mut self) {
+        self.active_loans = self.active_loans.saturating_sub(1);
+    }
+
+    // Exposure caps on new loans, each cap is disabled when set to 0
+    pub fn check_lending_caps(&self, principal: u64, current_time: i64) -> Result<()> {
+        if self.max_active_loans > 0 {
+            require!(self.active_loans < self.max_active_loans, ErrorCode::MaxActiveLoansReached);
+        }
+
+        if self.max_utilization_bps > 0 {
+            let lent_after = self.principal_outstanding as u128 + principal as u128;
+            let total_assets = self.total_assets() as u128;
+            require!(
+                lent_after * BPS_DENOMINATOR as u128 <= total_assets * self.max_utilization_bps as u128,
+                ErrorCode::UtilizationCapExceeded
+            );
+        }
+
+        if self.max_lent_per_window > 0 {
+            let window_open = current_time < self.window_start.saturating_add(self.lending_window);
+            let window_lent = if window_open { self.window_lent } else { 0 };
+            require!(
+                window_lent.saturating_add(principal) <= self.max_lent_per_window,
+                ErrorCode::LendingWindowCapExceeded
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_lending_caps(max_utilization_bps: u64, max_lent_per_window: u64, lending_window: i64) -> Result<()> {
+        require!(max_utilization_bps <= BPS_DENOMINATOR, ErrorCode::InvalidLendingCaps);
+        require!(lending_window >= 0, ErrorCode::InvalidLendingCaps);
+        require!(max_lent_per_window == 0 || lending_window > 0, ErrorCode::InvalidLendingCaps);
+
+        Ok(())
+    }
+
+    pub fn record_repayment(&mut self, interest_paid: u64, principal_paid: u64) {
+        self.amount += interest_paid + principal_paid;
+        self.interest_accumulated += interest_paid;
+        self.principal_outstanding = self.principal_outstanding.saturating_sub(principal_paid);
+        self.lifetime_repaid += principal_paid;
+    }
+
+    pub fn record_loss(&mut self, principal_lost: u64) {
+        self.principal_outstanding = self.principal_outstanding.saturating_sub(principal_lost);
+        self.realized_losses += principal_lost;
+        self.bad_debt_outstanding += principal_lost;
+        self.bad_debt_total += principal_lost;
+    }
+
+    // Insurance paid back into the vault against outstanding bad debt
+    pub fn record_bad_debt_covered(&mut self, amount: u64) {
+        self.amount += amount;
+        self.bad_debt_outstanding -= amount;
+    }
+
+    // Idle liquidity not owed to queued withdrawals
+    pub fn available_liquidity(&self) -> u64 {
+        self.amount
+            .saturating_sub(self.withdrawals_reserved)
+            .saturating_sub(self.withdrawals_pending)
+    }
+
+    // Assets backing the liquidity provider shares, idle liquidity plus principal lent out less queued withdrawals
+    pub fn total_assets(&self) -> u64 {
+        self.amount
+            .saturating_add(self.principal_outstanding)
+            .saturating_sub(self.withdrawals_reserved)
+            .saturating_sub(self.withdrawals_pending)
+    }
+
+    // Shares and assets are priced with VIRTUAL_LIQUIDITY added to both sides, an empty vault
+    // prices shares 1:1 and inflating the price of the first shares costs the attacker most of it
+    pub fn shares_for_assets(&self, amount: u64, share_supply: u64) -> Result<u64> {
+        (amount as u128)
+            .checked_mul(share_supply as u128 + VIRTUAL_LIQUIDITY as u128)
+            .and_then(|value| value.checked_div(self.total_assets() as u128 + VIRTUAL_LIQUIDITY as u128))
+            .and_then(|value| value.try_into().ok())
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+
+    // Shares to burn for a withdrawal of amount, rounded up in favour of the remaining holders
+    pub fn shares_for_withdrawal(&self, amount: u64, share_supply: u64) -> Result<u64> {
+        let total_assets = self.total_assets() as u128 + VIRTUAL_LIQUIDITY as u128;
+
+        (amount as u128)
+            .checked_mul(share_supply as u128 + VIRTUAL_LIQUIDITY as u128)
+            .and_then(|value| value.checked_add(total_assets - 1))
+            .and_then(|value| value.checked_div(total_assets))
+            .and_then(|value| value.try_into().ok())
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+
+    pub fn assets_for_shares(&self, shares: u64, share_supply: u64) -> Result<u64> {
+        require!(shares <= share_supply, ErrorCode::InvalidShareAmount);
+
+        (shares as u128)
+            .checked_mul(self.total_assets() as u128 + VIRTUAL_LIQUIDITY as u128)
+            .and_then(|value| value.checked_div(share_supply as u128 + VIRTUAL_LIQUIDITY as u128))
+            .and_then(|value| value.try_into().ok())
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+
+    pub fn validate_fee_schedule(fee_bps: u64, min_fee: u64, max_fee: u64) -> Result<()> {
+        require!(fee_bps <= BPS_DENOMINATOR, ErrorCode::InvalidFee);
+        require!(max_fee == 0 || min_fee <= max_fee, ErrorCode::InvalidFee);
+
+        Ok(())
+    }
+
+    pub fn validate_fee_split(treasury_bps: u64, insurance_bps: u64, referrer_bps: u64) -> Result<()> {
+        let total_bps = treasury_bps
+            .checked_add(insurance_bps)
+            .and_then(|value| value.checked_add(referrer_bps))
+            .ok_or(ErrorCode::InvalidFee)?;
+        require!(total_bps <= BPS_DENOMINATOR, ErrorCode::InvalidFee);
+
+        Ok(())
+    }
+
+    // Split a fee between treasury, insurance fund, referrer and the service vault
+    pub fn split_fee(&self, fee: u64, has_referrer: bool) -> Result<FeeSplit> {
+        let share = |bps: u64| -> Result<u64> {
+            (fee as u128)
+                .checked_mul(bps as u128)
+                .and_then(|value| value.checked_div(BPS_DENOMINATOR as u128))
+                .and_then(|value| value.try_into().ok())
+                .ok_or(error!(ErrorCode::InvalidFee))
+        };
+
+        let treasury = share(self.treasury_fee_bps)?;
+        let insurance = share(self.insurance_fee_bps)?;
+        let referrer = if has_referrer { share(self.referrer_fee_bps)? } else { 0 };
+        let vault = fee
+            .checked_sub(treasury + insurance + referrer)
+            .ok_or(ErrorCode::InvalidFee)?;
+
+        Ok(FeeSplit { treasury, insurance, referrer, vault })
+    }
+
+    // Share of the recovered sol paid to the liquidator
+    pub fn liquidation_bounty(&self, sol_recovered: u64) -> Result<u64> {
+        Self::bps_of(sol_recovered, self.liquidation_bounty_bps)
+    }
+
+    // Least sol a liquidation has to recover where the pool TWAP values it at expected_amount
+    pub fn min_liquidation_amount_out(&self, expected_amount: u64) -> Result<u64> {
+        Self::bps_of(expected_amount, BPS_DENOMINATOR.saturating_sub(self.liquidation_slippage_bps))
+    }
+
+    pub fn liquidation_penalty(&self, sol_recovered: u64) -> Result<u64> {
+        Self::bps_of(sol_recovered, self.liquidation_penalty_bps)
+    }
+
+    pub fn bps_of(amount: u64, bps: u64) -> Result<u64> {
+        let share = (amount as u128)
+            .checked_mul(bps as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+
+        Ok(share as u64)
+    }
+
+    // Fixed fee plus the basis point share of the borrowed sol, clamped to the floor and ceiling
+    pub fn compute_service_fee(&self, init_sol_amount: u64) -> Result<u64> {
+        let proportional_fee: u64 = (init_sol_amount as u128)
+            .checked_mul(self.service_fee_bps as u128)
+            .and_then(|value| value.checked_div(BPS_DENOMINATOR as u128))
+            .and_then(|value| value.try_into().ok())
+            .ok_or(ErrorCode::InvalidFee)?;
+        let mut fee = self.service_fee
+            .checked_add(proportional_fee)
+            .ok_or(ErrorCode::InvalidFee)?;
+
+        fee = fee.max(self.min_service_fee);
+        if self.max_service_fee > 0 {
+            fee = fee.min(self.max_service_fee);
+        }
+
+        Ok(fee)
+    }
 }
 impl PoolLoan {
     pub const LEN: usize = 32 + // user
@@ -40,5 +606,566 @@ impl PoolLoan {
                            8 +  // init_token_amount
                            8 +  // loan_start_time
                            8 +  // loan_duration
-                           1;   // is_repaid
+                           1 +  // is_repaid
+                           2 +  // tier_id
+                           1 +  // extension_count
+                           8 +  // principal_outstanding
+                           8 +  // interest_rate_bps
+                           8 +  // interest_accrued
+                           8 +  // last_accrual_time
+                           1 +  // late_fee_charged
+                           1 +  // in_auction
+                           8 +  // shortfall
+                           8;   // bad_debt
+
+    pub fn deadline(&self) -> i64 {
+        self.loan_start_time.saturating_add(self.loan_duration)
+    }
+
+    // End of the grace period, liquidation is allowed after this
+    pub fn grace_end(&self, grace_period: i64) -> i64 {
+        self.deadline().saturating_add(grace_period)
+    }
+
+    // Add the late fee to the interest owed once the loan is past its deadline, returns the fee charged now
+    pub fn charge_late_fee(&mut self, current_time: i64, late_fee_bps: u64) -> Result<u64> {
+        if self.late_fee_charged || current_time <= self.deadline() {
+            return Ok(0);
+        }
+
+        let late_fee: u64 = (self.amount_owed() as u128)
+            .checked_mul(late_fee_bps as u128)
+            .map(|value| value / BPS_DENOMINATOR as u128)
+            .and_then(|value| value.try_into().ok())
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.interest_accrued = self.interest_accrued
+            .checked_add(late_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.late_fee_charged = true;
+
+        Ok(late_fee)
+    }
+
+    // Escrowed lp worth less than the configured share of the sol lent, a threshold of 0 disables the check
+    pub fn is_below_health_threshold(&self, escrowed_sol: u64, health_threshold_bps: u64) -> Result<bool> {
+        if health_threshold_bps == 0 {
+            return Ok(false);
+        }
+
+        Ok(escrowed_sol < Config::bps_of(self.init_sol_amount, health_threshold_bps)?)
+    }
+
+    // Checks the loan can be liquidated at current_time and returns whether it is an early liquidation.
+    // Loans past their grace period can always be liquidated, earlier only once the escrowed sol valued
+    // at the pool TWAP falls below the health threshold. A running auction blocks it until it expires
+    pub fn check_liquidatable(
+        &self,
+        config: &Config,
+        twap_sol_value: Option<u64>,
+        auction: Option<&LiquidationAuction>,
+        current_time: i64,
+    ) -> Result<bool> {
+        require!(!self.is_repaid, ErrorCode::LoanAlreadyRepaid);
+        if self.in_auction {
+            require!(
+                auction.is_some_and(|auction| auction.is_expired(current_time)),
+                ErrorCode::AuctionInProgress
+            );
+        }
+
+        let early_liquidation = match twap_sol_value {
+            Some(value) => current_time <= self.grace_end(config.grace_period)
+                && self.is_below_health_threshold(value, config.health_threshold_bps)?,
+            None => false,
+        };
+        if !early_liquidation {
+            require!(current_time > self.deadline(), ErrorCode::LoanNotExpired);
+            require!(current_time > self.grace_end(config.grace_period), ErrorCode::LoanInGracePeriod);
+        }
+
+        Ok(early_liquidation)
+    }
+
+    // Accrue interest on the outstanding principal up to current_time
+    pub fn accrue_interest(&mut self, current_time: i64) -> Result<()> {
+        let elapsed = current_time.saturating_sub(self.last_accrual_time).max(0) as u128;
+        let interest: u64 = (self.principal_outstanding as u128)
+            .checked_mul(self.interest_rate_bps as u128)
+            .and_then(|value| value.checked_mul(elapsed))
+            .and_then(|value| value.checked_div(BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128))
+            .and_then(|value| value.try_into().ok())
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        self.interest_accrued = self.interest_accrued
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.last_accrual_time = current_time;
+
+        Ok(())
+    }
+
+    // Principal plus accrued interest, call accrue_interest first
+    pub fn amount_owed(&self) -> u64 {
+        self.principal_outstanding.saturating_add(self.interest_accrued)
+    }
+
+    // Apply a repayment to accrued interest first and then principal, returns (interest_paid, principal_paid)
+    pub fn apply_repayment(&mut self, amount: u64) -> (u64, u64) {
+        let interest_paid = amount.min(self.interest_accrued);
+        self.interest_accrued -= interest_paid;
+        let principal_paid = (amount - interest_paid).min(self.principal_outstanding);
+        self.principal_outstanding -= principal_paid;
+
+        (interest_paid, principal_paid)
+    }
+}
+impl LoanTierParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.sol_amount > 0, ErrorCode::InvalidInitSolAmount);
+        require!(self.min_duration > 0, ErrorCode::InvalidDuration);
+        require!(self.max_duration >= self.min_duration, ErrorCode::InvalidDuration);
+        require!(
+            self.max_extensions == 0 || self.extension_duration > 0,
+            ErrorCode::InvalidDuration
+        );
+
+        Ok(())
+    }
+}
+impl LoanTier {
+    pub const LEN: usize = 2 + // tier_id
+                           8 + // sol_amount
+                           8 + // min_duration
+                           8 + // max_duration
+                           8 + // service_fee
+                           1 + // is_enabled
+                           8 + // extension_duration
+                           8 + // extension_fee
+                           1;  // max_extensions
+
+    pub fn apply(&mut self, params: &LoanTierParams) {
+        self.sol_amount = params.sol_amount;
+        self.min_duration = params.min_duration;
+        self.max_duration = params.max_duration;
+        self.service_fee = params.service_fee;
+        self.extension_duration = params.extension_duration;
+        self.extension_fee = params.extension_fee;
+        self.max_extensions = params.max_extensions;
+    }
+
+    // Tier fee scaled by the multiplier of the chosen duration
+    pub fn fee_for(&self, option: &LoanDurationOption) -> Option<u64> {
+        (self.service_fee as u128)
+            .checked_mul(option.fee_multiplier_bps as u128)?
+            .checked_div(BPS_DENOMINATOR as u128)?
+            .try_into()
+            .ok()
+    }
+}
+impl WithdrawalQueue {
+    pub const LEN: usize = 8 + // next_request_id
+                           4 + // requests vec length
+                           MAX_WITHDRAWAL_REQUESTS * (
+                               8 +  // id
+                               32 + // owner
+                               8 +  // amount
+                               8    // requested_at
+                           );
+
+    pub fn is_pending(&self, request_id: u64) -> bool {
+        self.requests.iter().any(|request| request.id == request_id)
+    }
+}
+impl WithdrawalTicket {
+    pub const LEN: usize = 8 +  // id
+                           32 + // owner
+                           8 +  // amount
+                           8;   // requested_at
+}
+impl CreatorStats {
+    pub const LEN: usize = 32 + // creator
+                           8;   // active_loans
+
+    pub fn record_loan(&mut self, max_loans_per_creator: u64) -> Result<()> {
+        if max_loans_per_creator > 0 {
+            require!(self.active_loans < max_loans_per_creator, ErrorCode::CreatorLoanCapReached);
+        }
+        self.active_loans += 1;
+
+        Ok(())
+    }
+
+    pub fn record_loan_closed(&mut self) {
+        self.active_loans = self.active_loans.saturating_sub(1);
+    }
+}
+impl PendingAction {
+    pub const LEN: usize = 8 +  // id
+                           32 + // proposer
+                           AdminAction::LEN +
+                           8 +  // queued_at
+                           8;   // eta
+}
+impl AdminAction {
+    pub const LEN: usize = 1 + // variant
+                           32 * MAX_ADMIN_SIGNERS + 1; // largest variant, SetAdminSigners
+
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            AdminAction::UpdateFeeSchedule { fee_bps, min_fee, max_fee, .. } => {
+                Config::validate_fee_schedule(fee_bps, min_fee, max_fee)
+            }
+            AdminAction::UpdateFeeSplit { treasury_fee_bps, insurance_fee_bps, referrer_fee_bps } => {
+                Config::validate_fee_split(treasury_fee_bps, insurance_fee_bps, referrer_fee_bps)
+            }
+            AdminAction::UpdateInterestRate { interest_rate_bps } => {
+                require!(interest_rate_bps <= MAX_INTEREST_RATE_BPS, ErrorCode::InvalidInterestRate);
+                Ok(())
+            }
+            AdminAction::UpdateLendingCaps { max_utilization_bps, max_lent_per_window, lending_window, .. } => {
+                Config::validate_lending_caps(max_utilization_bps, max_lent_per_window, lending_window)
+            }
+            AdminAction::ProposeAdmin { new_admin } => {
+                require!(new_admin != Pubkey::default(), ErrorCode::InvalidAdmin);
+                Ok(())
+            }
+            AdminAction::SetSyncer { .. } | AdminAction::SetVerifier { .. } => Ok(()),
+            AdminAction::SetTimelock { delay, .. } => {
+                require!((0..=MAX_TIMELOCK_DELAY).contains(&delay), ErrorCode::InvalidTimelock);
+                Ok(())
+            }
+            AdminAction::Withdraw { amount, .. } | AdminAction::WithdrawTreasury { amount, .. } => {
+                require!(amount > 0, ErrorCode::InsufficientBalance);
+                Ok(())
+            }
+            AdminAction::AddGuardian { guardian } => {
+                require!(guardian != Pubkey::default(), ErrorCode::InvalidAdmin);
+                Ok(())
+            }
+            AdminAction::RemoveGuardian { .. } => Ok(()),
+            AdminAction::UpdateLiquidationBounty { bounty_bps } => {
+                require!(bounty_bps <= MAX_LIQUIDATION_BOUNTY_BPS, ErrorCode::InvalidLiquidationParams);
+                Ok(())
+            }
+            AdminAction::UpdateLiquidationPenalty { penalty_bps } => {
+                require!(penalty_bps <= MAX_LIQUIDATION_PENALTY_BPS, ErrorCode::InvalidLiquidationParams);
+                Ok(())
+            }
+            AdminAction::UpdateAuctionParams { decay_period, start_price_bps, floor_price_bps, max_duration, .. } => {
+                require!((0..=MAX_AUCTION_DECAY_PERIOD).contains(&decay_period), ErrorCode::InvalidAuctionParams);
+                require!(
+                    decay_period == 0 || (decay_period..=MAX_AUCTION_DURATION).contains(&max_duration),
+                    ErrorCode::InvalidAuctionParams
+                );
+                require!(
+                    floor_price_bps > 0 && floor_price_bps <= start_price_bps && start_price_bps <= MAX_AUCTION_START_PRICE_BPS,
+                    ErrorCode::InvalidAuctionParams
+                );
+                Ok(())
+            }
+            AdminAction::UpdateHealthThreshold { health_threshold_bps } => {
+                require!(health_threshold_bps < BPS_DENOMINATOR, ErrorCode::InvalidHealthThreshold);
+                Ok(())
+            }
+            AdminAction::UpdateLiquidationSlippage { slippage_bps } => {
+                require!(
+                    slippage_bps > 0 && slippage_bps <= MAX_LIQUIDATION_SLIPPAGE_BPS,
+                    ErrorCode::InvalidLiquidationParams
+                );
+                Ok(())
+            }
+            AdminAction::UpdateLoanTier { params, .. } => params.validate(),
+            AdminAction::SetLoanTierEnabled { .. } => Ok(()),
+            AdminAction::SetLoanDurations { options, count } => {
+                require!(count as usize <= MAX_LOAN_DURATIONS, ErrorCode::InvalidDuration);
+                LoanDurations::validate_options(&options[..count as usize])
+            }
+            AdminAction::UpdateGracePeriod { grace_period, late_fee_bps } => {
+                require!((0..=MAX_GRACE_PERIOD).contains(&grace_period), ErrorCode::InvalidLiquidationParams);
+                require!(late_fee_bps <= MAX_LATE_FEE_BPS, ErrorCode::InvalidLiquidationParams);
+                Ok(())
+            }
+            AdminAction::Unpause { operations } => {
+                require!(operations & !PAUSE_ALL == 0, ErrorCode::InvalidPauseMask);
+                Ok(())
+            }
+            AdminAction::SetAdminSigners { signers, threshold } => {
+                let signers: Vec<&Pubkey> = signers.iter().filter(|key| **key != Pubkey::default()).collect();
+                for (index, signer) in signers.iter().enumerate() {
+                    require!(!signers[..index].contains(signer), ErrorCode::InvalidAdminSigners);
+                }
+                require!(threshold as usize <= signers.len(), ErrorCode::InvalidAdminSigners);
+                Ok(())
+            }
+        }
+    }
+}
+impl AdminProposal {
+    pub const LEN: usize = 8 +  // id
+                           32 + // proposer
+                           AdminAction::LEN +
+                           32 * MAX_ADMIN_SIGNERS + // approvals
+                           8 +  // created_at
+                           8;   // threshold_reached_at
+
+    // Approvals from keys that are still admin signers
+    pub fn approval_count(&self, config: &Config) -> usize {
+        self.approvals.iter().filter(|key| config.is_admin_signer(key)).count()
+    }
+
+    pub fn approve(&mut self, signer: Pubkey) -> Result<()> {
+        require!(!self.approvals.contains(&signer), ErrorCode::AlreadyApproved);
+
+        let slot = self.approvals
+            .iter_mut()
+            .find(|key| **key == Pubkey::default())
+            .ok_or(ErrorCode::AlreadyApproved)?;
+        *slot = signer;
+
+        Ok(())
+    }
+}
+impl LiquidationAuction {
+    pub const LEN: usize = 32 + // pool_loan
+                           32 + // starter
+                           8 +  // lp_amount
+                           8 +  // start_price
+                           8 +  // floor_price
+                           1 +  // decay
+                           8 +  // decay_period
+                           8 +  // start_time
+                           8;   // expires_at
+
+    pub fn is_expired(&self, current_time: i64) -> bool {
+        current_time > self.expires_at
+    }
+
+    // Price in wrap sol a bidder pays at current_time, never below the floor
+    pub fn current_price(&self, current_time: i64) -> u64 {
+        let elapsed = current_time.saturating_sub(self.start_time).max(0) as u128;
+        let period = self.decay_period.max(1) as u128;
+        let start_price = self.start_price as u128;
+        let floor_price = self.floor_price as u128;
+
+        let price = match self.decay {
+            AuctionDecay::Linear => {
+                let decayed = (start_price - floor_price) * elapsed.min(period) / period;
+                start_price - decayed
+            }
+            AuctionDecay::Exponential => {
+                // Halve once per full period and interpolate linearly within the current one
+                let halvings = elapsed / period;
+                if halvings >= 64 {
+                    0
+                } else {
+                    let period_price = start_price >> halvings;
+                    period_price - (period_price / 2) * (elapsed % period) / period
+                }
+            }
+        };
+
+        price.max(floor_price) as u64
+    }
+}
+impl LoanDurations {
+    pub const LEN: usize = 4 + // options vec length
+                           MAX_LOAN_DURATIONS * (
+                               8 + // duration
+                               4   // fee_multiplier_bps
+                           );
+
+    pub fn validate_options(options: &[LoanDurationOption]) -> Result<()> {
+        require!(!options.is_empty() && options.len() <= MAX_LOAN_DURATIONS, ErrorCode::InvalidDuration);
+
+        for (index, option) in options.iter().enumerate() {
+            require!(option.duration > 0, ErrorCode::InvalidDuration);
+            require!(option.fee_multiplier_bps > 0, ErrorCode::InvalidFee);
+            require!(
+                options[..index].iter().all(|other| other.duration != option.duration),
+                ErrorCode::InvalidDuration
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn find(&self, duration: i64) -> Option<&LoanDurationOption> {
+        self.options.iter().find(|option| option.duration == duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_config() -> Config {
+        Config::deserialize(&mut &[0u8; Config::LEN][..]).unwrap()
+    }
+
+    // One sol lent at 10% a year for 30 days, starting at time 0
+    fn pool_loan() -> PoolLoan {
+        let mut pool_loan = PoolLoan::deserialize(&mut &[0u8; PoolLoan::LEN][..]).unwrap();
+        pool_loan.init_sol_amount = 1_000_000_000;
+        pool_loan.principal_outstanding = 1_000_000_000;
+        pool_loan.interest_rate_bps = 1_000;
+        pool_loan.loan_duration = 30 * 24 * 60 * 60;
+        pool_loan
+    }
+
+    fn auction(decay: AuctionDecay) -> LiquidationAuction {
+        LiquidationAuction {
+            pool_loan: Pubkey::default(),
+            starter: Pubkey::default(),
+            lp_amount: 1,
+            start_price: 1_000,
+            floor_price: 100,
+            decay,
+            decay_period: 100,
+            start_time: 1_000,
+            expires_at: 10_000,
+        }
+    }
+
+    #[test]
+    fn linear_auction_price_decays_to_the_floor() {
+        let auction = auction(AuctionDecay::Linear);
+
+        assert_eq!(auction.current_price(900), 1_000);
+        assert_eq!(auction.current_price(1_000), 1_000);
+        assert_eq!(auction.current_price(1_050), 550);
+        assert_eq!(auction.current_price(1_100), 100);
+        assert_eq!(auction.current_price(5_000), 100);
+    }
+
+    #[test]
+    fn exponential_auction_price_halves_each_period() {
+        let auction = auction(AuctionDecay::Exponential);
+
+        assert_eq!(auction.current_price(1_000), 1_000);
+        assert_eq!(auction.current_price(1_050), 750);
+        assert_eq!(auction.current_price(1_100), 500);
+        assert_eq!(auction.current_price(1_150), 375);
+        assert_eq!(auction.current_price(1_200), 250);
+    }
+
+    #[test]
+    fn exponential_auction_price_is_clamped_to_the_floor() {
+        let auction = auction(AuctionDecay::Exponential);
+
+        assert_eq!(auction.current_price(1_400), 100);
+        assert_eq!(auction.current_price(1_000 + 64 * 100), 100);
+        assert_eq!(auction.current_price(i64::MAX), 100);
+    }
+
+    #[test]
+    fn interest_accrues_per_second_on_the_outstanding_principal() {
+        let mut pool_loan = pool_loan();
+
+        pool_loan.accrue_interest(SECONDS_PER_YEAR as i64 / 2).unwrap();
+        assert_eq!(pool_loan.interest_accrued, 50_000_000);
+        pool_loan.accrue_interest(SECONDS_PER_YEAR as i64).unwrap();
+        assert_eq!(pool_loan.interest_accrued, 100_000_000);
+        assert_eq!(pool_loan.amount_owed(), 1_100_000_000);
+
+        // A clock behind the last accrual adds nothing
+        pool_loan.accrue_interest(0).unwrap();
+        assert_eq!(pool_loan.interest_accrued, 100_000_000);
+    }
+
+    #[test]
+    fn repayments_pay_interest_before_principal() {
+        let mut pool_loan = pool_loan();
+        pool_loan.interest_accrued = 100;
+
+        assert_eq!(pool_loan.apply_repayment(60), (60, 0));
+        assert_eq!(pool_loan.apply_repayment(1_040), (40, 1_000));
+        assert_eq!(pool_loan.principal_outstanding, 999_999_000);
+
+        // Anything above the amount owed is not applied
+        assert_eq!(pool_loan.apply_repayment(u64::MAX), (0, 999_999_000));
+        assert_eq!(pool_loan.amount_owed(), 0);
+    }
+
+    #[test]
+    fn late_fee_is_charged_once_after_the_deadline() {
+        let mut pool_loan = pool_loan();
+        pool_loan.interest_accrued = 100_000_000;
+        let deadline = pool_loan.deadline();
+
+        assert_eq!(pool_loan.charge_late_fee(deadline, 500).unwrap(), 0);
+        assert_eq!(pool_loan.charge_late_fee(deadline + 1, 500).unwrap(), 55_000_000);
+        assert_eq!(pool_loan.interest_accrued, 155_000_000);
+        assert_eq!(pool_loan.charge_late_fee(deadline + 2, 500).unwrap(), 0);
+        assert_eq!(pool_loan.interest_accrued, 155_000_000);
+    }
+
+    #[test]
+    fn shares_are_priced_one_to_one_in_an_empty_vault() {
+        let config = empty_config();
+
+        assert_eq!(config.shares_for_assets(1_000_000_000, 0).unwrap(), 1_000_000_000);
+    }
+
+    #[test]
+    fn shares_follow_the_vault_assets() {
+        let mut config = empty_config();
+        config.amount = 1_000_000_000;
+        config.principal_outstanding = 1_000_000_000;
+        let share_supply = 1_000_000_000;
+
+        // Assets doubled since the shares were minted
+        let shares = config.shares_for_assets(1_000_000_000, share_supply).unwrap();
+        assert_eq!(shares, 500_249_875);
+        assert!(config.shares_for_withdrawal(1_000_000_000, share_supply).unwrap() > shares);
+
+        // Queued withdrawals no longer back the shares
+        config.withdrawals_pending = 1_000_000_000;
+        assert_eq!(config.shares_for_assets(1_000_000_000, share_supply).unwrap(), 1_000_000_000);
+    }
+
+    #[test]
+    fn inflated_first_share_does_not_take_the_next_deposit() {
+        // A single share backed by a large donation
+        let mut config = empty_config();
+        config.amount = 1_000_000_000;
+        let deposit = 1_000_000_000;
+
+        let shares = config.shares_for_assets(deposit, 1).unwrap();
+        assert!(shares > 0);
+
+        config.amount += deposit;
+        let redeemable = config.assets_for_shares(shares, shares + 1).unwrap();
+        assert!(redeemable >= deposit / 100 * 99);
+    }
+
+    #[test]
+    fn expired_loans_are_liquidatable_without_a_twap() {
+        let mut config = empty_config();
+        config.grace_period = 100;
+        config.health_threshold_bps = 8_000;
+        let pool_loan = pool_loan();
+        let grace_end = pool_loan.grace_end(config.grace_period);
+
+        assert!(pool_loan.check_liquidatable(&config, None, None, grace_end).is_err());
+        assert!(pool_loan.check_liquidatable(&config, Some(1_000_000_000), None, grace_end).is_err());
+        assert!(!pool_loan.check_liquidatable(&config, None, None, grace_end + 1).unwrap());
+
+        // Below the health threshold only with a TWAP to value the escrowed lp
+        assert!(pool_loan.check_liquidatable(&config, Some(700_000_000), None, 0).unwrap());
+    }
+
+    #[test]
+    fn running_auction_blocks_liquidation_until_it_expires() {
+        let config = empty_config();
+        let mut pool_loan = pool_loan();
+        pool_loan.in_auction = true;
+        let current_time = pool_loan.deadline() + 1;
+        let mut auction = auction(AuctionDecay::Linear);
+        auction.start_time = current_time;
+        auction.expires_at = current_time + 1_000;
+
+        assert!(pool_loan.check_liquidatable(&config, None, None, current_time).is_err());
+        assert!(pool_loan.check_liquidatable(&config, None, Some(&auction), current_time).is_err());
+        assert!(pool_loan.check_liquidatable(&config, None, Some(&auction), auction.expires_at + 1).is_ok());
+    }
 }
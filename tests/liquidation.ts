@@ -0,0 +1,338 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { Smartv21 } from "../target/types/smartv21";
+import { getAuthAddress, getPoolAddress, getPoolLpMintAddress, getPoolVaultAddress, getOrcleAccountAddress, getBlockTimestamp, accountExist, wrap_sol } from "./utils";
+import { PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { getAccount, getAssociatedTokenAddressSync, getOrCreateAssociatedTokenAccount, NATIVE_MINT, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { cpSwapProgram, configAddress } from "./config";
+import { ASSOCIATED_PROGRAM_ID } from "@coral-xyz/anchor/dist/cjs/utils/token";
+import { expect } from "chai";
+
+// These run against pools that already have a loan in the right state, each test is skipped when its pool is not set:
+//   LIQUIDATION_TOKEN_MINT  token of a wsol pool whose loan is past its grace period
+//   GRACE_TOKEN_MINT        token of a wsol pool whose loan is past its deadline but still in its grace period
+//   AUCTION_TOKEN_MINT      token of a wsol pool whose loan is past its grace period, with auctions enabled
+describe("liquidation", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+
+  const program = anchor.workspace.Smartv21 as Program<Smartv21>;
+  const connection = anchor.getProvider().connection;
+  const owner = anchor.Wallet.local().payer;
+  const memoProgram = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+  const pda = (...seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const config = pda(Buffer.from("config"));
+  const serviceVault = pda(Buffer.from("vault"));
+  const treasuryVault = pda(Buffer.from("treasury"));
+  const withdrawalQueue = pda(Buffer.from("withdrawal_queue"));
+
+  const tokenAmount = async (address: PublicKey) => new BN((await getAccount(connection, address)).amount.toString());
+
+  // Every account of the pool and its loan, token_0 is the lower mint like raydium orders them
+  async function loanAccounts(tokenMint: PublicKey) {
+    const [token0, token1] = Buffer.compare(NATIVE_MINT.toBuffer(), tokenMint.toBuffer()) < 0
+      ? [NATIVE_MINT, tokenMint]
+      : [tokenMint, NATIVE_MINT];
+    const [poolState] = await getPoolAddress(configAddress, token0, token1, cpSwapProgram);
+    const [lpMint] = await getPoolLpMintAddress(poolState, cpSwapProgram);
+    const [token0Vault] = await getPoolVaultAddress(poolState, token0, cpSwapProgram);
+    const [token1Vault] = await getPoolVaultAddress(poolState, token1, cpSwapProgram);
+    const [observationState] = await getOrcleAccountAddress(poolState, cpSwapProgram);
+    const [authority] = await getAuthAddress(cpSwapProgram);
+    const poolLoan = pda(Buffer.from("pool_loan"), poolState.toBuffer());
+    const loan = await program.account.poolLoan.fetch(poolLoan);
+
+    return {
+      token0,
+      token1,
+      poolState,
+      lpMint,
+      token0Vault,
+      token1Vault,
+      observationState,
+      authority,
+      poolLoan,
+      loan,
+      creator: loan.user,
+      serviceTokenLp: pda(Buffer.from("lp_token"), poolState.toBuffer()),
+      auction: pda(Buffer.from("liquidation_auction"), poolLoan.toBuffer()),
+      creatorStats: pda(Buffer.from("creator_stats"), loan.user.toBuffer()),
+    };
+  }
+
+  async function tokenProgramOf(mint: PublicKey) {
+    return (await connection.getAccountInfo(mint)).owner;
+  }
+
+  // Liquidate the whole escrowed position with the wallet as the liquidator
+  async function liquidate(accounts: Awaited<ReturnType<typeof loanAccounts>>, auctionStarter: PublicKey | null = null) {
+    const token0Program = await tokenProgramOf(accounts.token0);
+    const token1Program = await tokenProgramOf(accounts.token1);
+    const ownerToken0 = await getOrCreateAssociatedTokenAccount(connection, owner, accounts.token0, owner.publicKey, false, "confirmed", undefined, token0Program);
+    const ownerToken1 = await getOrCreateAssociatedTokenAccount(connection, owner, accounts.token1, owner.publicKey, false, "confirmed", undefined, token1Program);
+    const ownerLpToken = await getOrCreateAssociatedTokenAccount(connection, owner, accounts.lpMint, owner.publicKey);
+    const lpTokenAmount = await tokenAmount(accounts.serviceTokenLp);
+
+    return await program.methods
+      .liquidateLoan(lpTokenAmount, new BN(0), new BN(0), null)
+      .accounts({
+        config,
+        poolLoan: accounts.poolLoan,
+        serviceTokenLp: accounts.serviceTokenLp,
+        serviceVault,
+        withdrawalQueue,
+        creatorStats: accounts.creatorStats,
+        cpSwapProgram,
+        treasuryVault,
+        owner: owner.publicKey,
+        creator: accounts.creator,
+        creatorToken0: getAssociatedTokenAddressSync(accounts.token0, accounts.creator, false, token0Program),
+        creatorToken1: getAssociatedTokenAddressSync(accounts.token1, accounts.creator, false, token1Program),
+        authority: accounts.authority,
+        poolState: accounts.poolState,
+        ownerLpToken: ownerLpToken.address,
+        token0Account: ownerToken0.address,
+        token1Account: ownerToken1.address,
+        token0Vault: accounts.token0Vault,
+        token1Vault: accounts.token1Vault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgram2022: TOKEN_2022_PROGRAM_ID,
+        vault0Mint: accounts.token0,
+        vault1Mint: accounts.token1,
+        lpMint: accounts.lpMint,
+        memoProgram,
+        ammConfig: null,
+        observationState: accounts.observationState,
+        auction: auctionStarter ? accounts.auction : null,
+        auctionStarter,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+  }
+
+  async function loanHealth(accounts: Awaited<ReturnType<typeof loanAccounts>>) {
+    return await program.methods
+      .getLoanHealth()
+      .accounts({
+        config,
+        poolLoan: accounts.poolLoan,
+        serviceTokenLp: accounts.serviceTokenLp,
+        poolState: accounts.poolState,
+        token0Vault: accounts.token0Vault,
+        token1Vault: accounts.token1Vault,
+        observationState: accounts.observationState,
+        auction: (await accountExist(connection, accounts.auction)) ? accounts.auction : null,
+      })
+      .view();
+  }
+
+  async function expectProgramError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+    } catch (error) {
+      expect(error.error?.errorCode?.code ?? error.toString()).to.contain(code);
+      return;
+    }
+    expect.fail(`expected ${code}`);
+  }
+
+  async function findEvent(signature: string, name: string) {
+    const transaction = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    for (const event of parser.parseLogs(transaction.meta.logMessages)) {
+      if (event.name == name) {
+        return event.data as any;
+      }
+    }
+    expect.fail(`${name} not emitted`);
+  }
+
+  // Mirror of LiquidationAuction::current_price
+  function auctionPrice(auction: any, time: number) {
+    const elapsed = Math.max(time - auction.startTime.toNumber(), 0);
+    const period = Math.max(auction.decayPeriod.toNumber(), 1);
+    const startPrice = auction.startPrice.toNumber();
+    const floorPrice = auction.floorPrice.toNumber();
+
+    let price: number;
+    if (auction.decay.linear) {
+      price = startPrice - Math.floor((startPrice - floorPrice) * Math.min(elapsed, period) / period);
+    } else {
+      const halvings = Math.floor(elapsed / period);
+      if (halvings >= 64) {
+        price = 0;
+      } else {
+        const periodPrice = Math.floor(startPrice / 2 ** halvings);
+        price = periodPrice - Math.floor(Math.floor(periodPrice / 2) * (elapsed % period) / period);
+      }
+    }
+    return Math.max(price, floorPrice);
+  }
+
+  // What a settled loan moved between the vault accounting, the pool loan and the creator stats
+  async function snapshot(accounts: Awaited<ReturnType<typeof loanAccounts>>) {
+    return {
+      config: await program.account.config.fetch(config),
+      loan: await program.account.poolLoan.fetch(accounts.poolLoan),
+      creatorStats: (await accountExist(connection, accounts.creatorStats))
+        ? await program.account.creatorStats.fetch(accounts.creatorStats)
+        : null,
+      serviceVault: await tokenAmount(serviceVault),
+      treasuryVault: await tokenAmount(treasuryVault),
+    };
+  }
+
+  function expectSettled(before: Awaited<ReturnType<typeof snapshot>>, after: Awaited<ReturnType<typeof snapshot>>) {
+    expect(after.loan.isRepaid).to.be.true;
+    expect(after.loan.inAuction).to.be.false;
+    expect(after.config.activeLoans.toNumber()).to.equal(before.config.activeLoans.toNumber() - 1);
+    if (before.creatorStats) {
+      expect(after.creatorStats.activeLoans.toNumber()).to.equal(before.creatorStats.activeLoans.toNumber() - 1);
+    }
+
+    // The vault receives interest and principal, the principal left unpaid is the bad debt
+    const principalPaid = before.loan.principalOutstanding.sub(after.loan.principalOutstanding);
+    const interestPaid = after.config.interestAccumulated.sub(before.config.interestAccumulated);
+    expect(after.config.lifetimeRepaid.sub(before.config.lifetimeRepaid).toString()).to.equal(principalPaid.toString());
+    expect(after.serviceVault.sub(before.serviceVault).toString()).to.equal(principalPaid.add(interestPaid).toString());
+    expect(after.loan.badDebt.toString()).to.equal(after.loan.principalOutstanding.toString());
+    expect(after.config.badDebtOutstanding.sub(before.config.badDebtOutstanding).toString()).to.equal(after.loan.badDebt.toString());
+    expect(after.loan.shortfall.gte(after.loan.badDebt)).to.be.true;
+  }
+
+  it("Rejects liquidation during the grace period", async function () {
+    if (!process.env.GRACE_TOKEN_MINT) this.skip();
+    const accounts = await loanAccounts(new PublicKey(process.env.GRACE_TOKEN_MINT));
+    const { gracePeriod } = await program.account.config.fetch(config);
+    const deadline = accounts.loan.loanStartTime.add(accounts.loan.loanDuration).toNumber();
+    const now = await getBlockTimestamp(connection);
+    if (now <= deadline || now > deadline + gracePeriod.toNumber() || (await loanHealth(accounts)).isLiquidatable) this.skip();
+
+    await expectProgramError(liquidate(accounts), "LoanInGracePeriod");
+    await expectProgramError(
+      program.methods
+        .startLiquidationAuction()
+        .accounts({
+          config,
+          poolLoan: accounts.poolLoan,
+          serviceTokenLp: accounts.serviceTokenLp,
+          auction: accounts.auction,
+          starter: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc(),
+      "LoanInGracePeriod"
+    );
+  });
+
+  it("Settles a liquidation between the liquidator, vault, treasury and creator", async function () {
+    if (!process.env.LIQUIDATION_TOKEN_MINT) this.skip();
+    const accounts = await loanAccounts(new PublicKey(process.env.LIQUIDATION_TOKEN_MINT));
+    expect(accounts.loan.isRepaid).to.be.false;
+    expect((await loanHealth(accounts)).isLiquidatable).to.be.true;
+
+    const creatorSolAccount = getAssociatedTokenAddressSync(NATIVE_MINT, accounts.creator);
+    const creatorSolBefore = (await accountExist(connection, creatorSolAccount)) ? await tokenAmount(creatorSolAccount) : new BN(0);
+    const before = await snapshot(accounts);
+
+    const signature = await liquidate(accounts);
+    const event = await findEvent(signature, "LoanLiquidatedEvent");
+    const after = await snapshot(accounts);
+
+    expectSettled(before, after);
+    expect(event.liquidator.toBase58()).to.equal(owner.publicKey.toBase58());
+    expect(event.earlyLiquidation).to.be.false;
+    expect(event.shortfall.toString()).to.equal(after.loan.shortfall.toString());
+    expect(after.treasuryVault.sub(before.treasuryVault).toString()).to.equal(event.penalty.toString());
+    expect((await tokenAmount(creatorSolAccount)).sub(creatorSolBefore).toString()).to.equal(event.creatorSolAmount.toString());
+    expect((await tokenAmount(accounts.serviceTokenLp)).toNumber()).to.equal(0);
+
+    // Only a loan recovered in full leaves a surplus for the treasury and the creator
+    if (after.loan.shortfall.gtn(0)) {
+      expect(event.penalty.toNumber()).to.equal(0);
+      expect(event.creatorSolAmount.toNumber()).to.equal(0);
+    }
+  });
+
+  it("Runs a liquidation auction from start to settlement", async function () {
+    if (!process.env.AUCTION_TOKEN_MINT) this.skip();
+    const accounts = await loanAccounts(new PublicKey(process.env.AUCTION_TOKEN_MINT));
+    const { auctionMaxDuration } = await program.account.config.fetch(config);
+    expect(accounts.loan.isRepaid).to.be.false;
+
+    await program.methods
+      .startLiquidationAuction()
+      .accounts({
+        config,
+        poolLoan: accounts.poolLoan,
+        serviceTokenLp: accounts.serviceTokenLp,
+        auction: accounts.auction,
+        starter: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const auction = await program.account.liquidationAuction.fetch(accounts.auction);
+    expect((await program.account.poolLoan.fetch(accounts.poolLoan)).inAuction).to.be.true;
+    expect(auction.lpAmount.toString()).to.equal((await tokenAmount(accounts.serviceTokenLp)).toString());
+    expect(auction.startPrice.gte(auction.floorPrice)).to.be.true;
+    expect(auction.expiresAt.toNumber()).to.equal(auction.startTime.add(auctionMaxDuration).toNumber());
+    expect((await loanHealth(accounts)).isLiquidatable).to.be.false;
+
+    // A running auction blocks direct liquidation
+    await expectProgramError(liquidate(accounts, owner.publicKey), "AuctionInProgress");
+
+    await wrap_sol(owner, auction.startPrice.toNumber() / LAMPORTS_PER_SOL);
+    const bidderTokenAccount = getAssociatedTokenAddressSync(NATIVE_MINT, owner.publicKey);
+    const bidderLpToken = await getOrCreateAssociatedTokenAccount(connection, owner, accounts.lpMint, owner.publicKey);
+    const bid = (maxPrice: BN) =>
+      program.methods
+        .bidLiquidationAuction(maxPrice)
+        .accounts({
+          config,
+          poolLoan: accounts.poolLoan,
+          auction: accounts.auction,
+          starter: owner.publicKey,
+          serviceTokenLp: accounts.serviceTokenLp,
+          serviceVault,
+          treasuryVault,
+          withdrawalQueue,
+          creatorStats: accounts.creatorStats,
+          bidder: owner.publicKey,
+          bidderTokenAccount,
+          bidderLpToken: bidderLpToken.address,
+          creator: accounts.creator,
+          creatorTokenAccount: getAssociatedTokenAddressSync(NATIVE_MINT, accounts.creator),
+          wsolMint: NATIVE_MINT,
+          lpMint: accounts.lpMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    // The price never drops below the floor
+    if (auction.floorPrice.gtn(0)) {
+      await expectProgramError(bid(auction.floorPrice.subn(1)), "AuctionPriceAboveMax");
+    }
+
+    const before = await snapshot(accounts);
+    const lpBefore = await tokenAmount(bidderLpToken.address);
+    const bidTime = await getBlockTimestamp(connection);
+    const signature = await bid(auction.startPrice);
+    const event = await findEvent(signature, "LiquidationAuctionSettledEvent");
+    const after = await snapshot(accounts);
+
+    // The bidder paid the decayed price at the time of the bid
+    expect(event.price.toNumber()).to.equal(auctionPrice(auction, event.timestamp.toNumber()));
+    expect(event.price.toNumber()).to.be.at.most(auctionPrice(auction, bidTime));
+    expect(event.price.gte(auction.floorPrice)).to.be.true;
+
+    expectSettled(before, after);
+    expect(after.serviceVault.sub(before.serviceVault).add(event.penalty).add(event.creatorSolAmount).toString()).to.equal(event.price.toString());
+    expect(after.treasuryVault.sub(before.treasuryVault).toString()).to.equal(event.penalty.toString());
+    expect((await tokenAmount(bidderLpToken.address)).sub(lpBefore).toString()).to.equal(auction.lpAmount.toString());
+    expect(await accountExist(connection, accounts.auction)).to.be.false;
+  });
+});
@@ -79,6 +79,14 @@ describe("smartv21", () => {
         [Buffer.from("vault")],
         program.programId
       );
+      const [shareMint] = await PublicKey.findProgramAddress(
+        [Buffer.from("share_mint")],
+        program.programId
+      );
+      const [protocolShareAccount] = await PublicKey.findProgramAddress(
+        [Buffer.from("protocol_shares")],
+        program.programId
+      );
       
       const tx = await program.rpc.deposit(new anchor.BN(2.1 * LAMPORTS_PER_SOL), {
         accounts: {
@@ -87,7 +95,10 @@ describe("smartv21", () => {
           tokenMint: NATIVE_MINT,
           serviceVault,
           adminTokenAccount: adminTokenAccount.address,
-          tokenProgram: TOKEN_PROGRAM_ID
+          shareMint,
+          protocolShareAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId
         },
         signers: [owner]
       });
@@ -186,6 +197,29 @@ describe("smartv21", () => {
         program.programId
       );
 
+      const tierId = 0;
+      const tierIdBuffer = Buffer.alloc(2);
+      tierIdBuffer.writeUInt16LE(tierId);
+      const [loanTier] = await PublicKey.findProgramAddress(
+        [Buffer.from("loan_tier"), tierIdBuffer],
+        program.programId
+      );
+      const [loanDurations] = await PublicKey.findProgramAddress(
+        [Buffer.from("loan_durations")],
+        program.programId
+      );
+      const [creatorStats] = await PublicKey.findProgramAddress(
+        [Buffer.from("creator_stats"), user.publicKey.toBuffer()],
+        program.programId
+      );
+      const [treasuryVault] = await PublicKey.findProgramAddress(
+        [Buffer.from("treasury")],
+        program.programId
+      );
+      const [insuranceVault] = await PublicKey.findProgramAddress(
+        [Buffer.from("insurance")],
+        program.programId
+      );
      
       const serviceOwnerTokenLp = getAssociatedTokenAddressSync(
         lpMintAddress,
@@ -206,7 +240,13 @@ describe("smartv21", () => {
         .accounts({
             config,
             poolLoan,
+            loanTier,
+            creatorStats,
+            loanDurations,
             serviceVault,
+            treasuryVault,
+            insuranceVault,
+            referrerTokenAccount: null,
             cpSwapProgram,
             creator: user.publicKey,
             ammConfig: configAddress,
@@ -393,6 +433,14 @@ describe("smartv21", () => {
         [Buffer.from("vault")],
         program.programId
       );
+      const [withdrawalQueue] = await PublicKey.findProgramAddress(
+        [Buffer.from("withdrawal_queue")],
+        program.programId
+      );
+      const [creatorStats] = await PublicKey.findProgramAddress(
+        [Buffer.from("creator_stats"), user.publicKey.toBuffer()],
+        program.programId
+      );
 
        const token0 = new PublicKey("So11111111111111111111111111111111111111112");
       const token1 = new PublicKey("hoMehKwGNXVN9wzw36DjqUeAQWYFfocRJqJc4Jt9Fes")
@@ -478,6 +526,8 @@ describe("smartv21", () => {
             poolLoan,
             serviceTokenLp,
             serviceVault,
+            withdrawalQueue,
+            creatorStats,
             cpSwapProgram,
             owner: user.publicKey,
             authority: auth,
@@ -492,7 +542,8 @@ describe("smartv21", () => {
             vault0Mint: token0,
             vault1Mint: token1,
             lpMint: lpMintAddress,
-            memoProgram: new PublicKey('MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr')
+            memoProgram: new PublicKey('MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr'),
+            systemProgram: SystemProgram.programId
           },
           signers: [user]
         }
@@ -518,6 +569,14 @@ describe("smartv21", () => {
         [Buffer.from("vault")],
         program.programId
       );
+      const [withdrawalQueue] = await PublicKey.findProgramAddress(
+        [Buffer.from("withdrawal_queue")],
+        program.programId
+      );
+      const [creatorStats] = await PublicKey.findProgramAddress(
+        [Buffer.from("creator_stats"), user.publicKey.toBuffer()],
+        program.programId
+      );
       const token0 = NATIVE_MINT; 
       const token0Program = TOKEN_PROGRAM_ID;
       const token1 = new PublicKey('47fRyTShN9SQ7MFXTDa6NF6B68pYnxLqo1BLCR5q54uW');
@@ -591,17 +650,30 @@ describe("smartv21", () => {
       const vault1Info = await getAccount(connection, vault1);
       const vault1Amount = vault1Info.amount;
 
+      const [treasuryVault] = await PublicKey.findProgramAddress(
+        [Buffer.from("treasury")],
+        program.programId
+      );
+      const [observationState] = await getOrcleAccountAddress(poolAddress, cpSwapProgram);
+
       const tx = await program.rpc.liquidateLoan(
         new anchor.BN(lpTokenAmount), 
         new anchor.BN(Number(vault0Amount) / 10),
-        new anchor.BN(Number(vault1Amount) / 10), {
+        new anchor.BN(Number(vault1Amount) / 10),
+        null, {
           accounts: {
             config,
             poolLoan,
             serviceTokenLp,
             serviceVault,
+            withdrawalQueue,
+            creatorStats,
             cpSwapProgram,
+            treasuryVault,
             owner: user.publicKey,
+            creator: user.publicKey,
+            creatorToken0,
+            creatorToken1,
             authority: auth,
             poolState: poolAddress,
             ownerLpToken: creatorLpTokenAddress,
@@ -614,7 +686,13 @@ describe("smartv21", () => {
             vault0Mint: token0,
             vault1Mint: token1,
             lpMint: lpMintAddress,
-            memoProgram: new PublicKey('MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr')
+            memoProgram: new PublicKey('MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr'),
+            ammConfig: null,
+            observationState,
+            auction: null,
+            auctionStarter: null,
+            associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+            systemProgram: SystemProgram.programId
           },
           signers: [user]
         }
@@ -639,6 +717,14 @@ describe("smartv21", () => {
         [Buffer.from("vault")],
         program.programId
       );
+      const [shareMint] = await PublicKey.findProgramAddress(
+        [Buffer.from("share_mint")],
+        program.programId
+      );
+      const [protocolShareAccount] = await PublicKey.findProgramAddress(
+        [Buffer.from("protocol_shares")],
+        program.programId
+      );
       
       const tx = await program.rpc.withdraw(new anchor.BN(2.1 * LAMPORTS_PER_SOL), {
         accounts: {
@@ -647,7 +733,10 @@ describe("smartv21", () => {
           tokenMint: NATIVE_MINT,
           serviceVault,
           adminTokenAccount: adminTokenAccount.address,
-          tokenProgram: TOKEN_PROGRAM_ID
+          shareMint,
+          protocolShareAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId
         },
         signers: [owner]
       });